@@ -15,3 +15,11 @@ fn get_role() -> Role {
 fn external_thing() -> SomeExternalType {
     todo!()
 }
+
+// In an inline module, which doesn't see the file's imports, the fix adds
+// the import inside the module
+mod settings {
+    pub fn defaults() -> Config {
+        Config { debug: false }
+    }
+}
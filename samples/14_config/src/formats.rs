@@ -0,0 +1,24 @@
+pub enum Format {
+    #[cfg(feature = "json")]
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+// `yaml` isn't an enabled feature, so this is never validated — should be SILENT
+#[cfg(feature = "yaml")]
+fn yaml_default() -> Format {
+    Format::Yml
+}
+
+// `json` is enabled, so this is validated: E0599
+#[cfg(feature = "json")]
+fn json_default() -> Format {
+    Format::Jsn
+}
+
+// Suppressed by rule ID rather than error code — should be SILENT
+#[cfg(feature = "json")]
+fn json_fallback() -> Format {
+    Format::Jsonn // rustpeek: allow(unknown-variant)
+}
@@ -0,0 +1,15 @@
+mod formats;
+
+// E0412 promoted to an error by [package.metadata.rustpeek.severity]
+fn default_format() -> Format {
+    Format::Json
+}
+
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+// E0061 is disabled in the config, by its rule ID — should be SILENT
+fn call_add() -> i32 {
+    add(1, 2, 3)
+}
@@ -0,0 +1,24 @@
+// rustc `#[allow(...)]` attributes silence the rules that mirror the lint
+
+// unused_imports covers unresolved imports — should be SILENT
+#[allow(unused_imports)]
+use crate::Circle;
+
+// dead_code covers names rustpeek would suggest importing — should be SILENT
+#[allow(dead_code)]
+fn outline(shape: Shape) {}
+
+pub struct Canvas;
+
+// An allow on the enclosing impl covers its methods — should be SILENT
+#[allow(unused)]
+impl Canvas {
+    pub fn draw(&self, shape: Shape) {}
+}
+
+// A lint no rule mirrors changes nothing: E0432
+#[allow(non_snake_case)]
+use crate::Square;
+
+// Not allowed: a SUGGESTION (E0412) to import Shape
+fn fill(shape: Shape) {}
@@ -0,0 +1,8 @@
+#![allow(unused_imports)]
+
+// `#![allow(unused_imports)]` above covers the whole file — should be SILENT
+use crate::Triangle;
+
+// The `#[allow(dead_code)]` on this module's declaration covers the whole
+// file — should be SILENT
+pub fn legacy(shape: Shape) {}
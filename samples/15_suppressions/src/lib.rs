@@ -0,0 +1,52 @@
+// rustpeek: allow-file(E0599)
+
+mod attributes;
+#[allow(dead_code)]
+mod legacy;
+
+pub enum Shape {
+    Circle,
+    Square,
+}
+
+// Suppressed for the whole file by allow-file — should be SILENT
+fn triangle() -> Shape {
+    Shape::Triangle
+}
+
+fn area(width: u32, height: u32) -> u32 {
+    width * height
+}
+
+// Suppressed by the comment on the preceding line — should be SILENT
+fn too_many() -> u32 {
+    // rustpeek: allow(E0061)
+    area(1, 2, 3)
+}
+
+// Suppressed by a trailing comment — should be SILENT
+fn too_few() -> u32 {
+    area(1) // rustpeek: allow(E0061)
+}
+
+// Not suppressed: E0061
+fn also_too_few() -> u32 {
+    area(2)
+}
+
+// Suppression that matches nothing: reported as unused
+// rustpeek: allow(E0063)
+fn fine() -> u32 {
+    area(1, 2)
+}
+
+// A trailing comment after a string with `//` in it — should be SILENT
+fn with_url() -> u32 {
+    let _url = "http://example.com"; area(1) // rustpeek: allow(E0061)
+}
+
+// Comment-like text in string and char literals isn't a suppression: E0061
+fn in_strings() -> u32 {
+    let _raw = r#"// rustpeek: allow(E0061)"#;
+    let _quote = '"'; area(3) + area("// rustpeek: allow(E0061)".len() as u32)
+}
@@ -0,0 +1,4 @@
+pub mod models;
+pub mod scoped;
+pub mod searcher;
+pub mod typos;
@@ -0,0 +1,5 @@
+pub struct User {
+    pub id: u64,
+}
+
+pub struct Instance;
@@ -0,0 +1,20 @@
+// Names brought in by a `use` inside a block — should be SILENT,
+// even though `Instance` is close to `Instant`
+use crate::models::Instance;
+
+pub fn elapsed() -> u64 {
+    use std::time::Instant;
+    let start: Instant = Instant::now();
+    start.elapsed().as_secs()
+}
+
+// A glob inside a block could bring in anything — should be SILENT
+pub fn sorted(values: &mut Vec<u32>) -> Ordering {
+    use std::cmp::*;
+    values.sort();
+    Ordering::Equal
+}
+
+pub fn make() -> Instance {
+    Instance
+}
@@ -0,0 +1,13 @@
+// A union is an item like any other — should be SILENT,
+// not "did you mean `SearcherKindFn`?"
+pub struct Searcher {
+    kind: SearcherKind,
+    call: SearcherKindFn,
+}
+
+union SearcherKind {
+    empty: (),
+    byte: u8,
+}
+
+type SearcherKindFn = fn(&Searcher) -> bool;
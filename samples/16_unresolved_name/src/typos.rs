@@ -0,0 +1,16 @@
+use crate::models::User;
+
+// Misspelled and nothing could provide them — should be ERRORS (E0412)
+// with a did-you-mean
+pub fn load(id: u64) -> Uesr {
+    User { id }
+}
+
+pub fn names() -> Vec<Strng> {
+    Vec::new()
+}
+
+// Generic parameters are in scope — should be SILENT
+pub fn first<Item: Clone>(items: &[Item]) -> Item {
+    items[0].clone()
+}
@@ -0,0 +1,5 @@
+// The module is already imported with a group: `Team` joins it, in sorted
+// position — should be a SUGGESTION (E0412) whose fix merges into the group
+use crate::models::{Role, User};
+
+pub fn assign(_user: User, _role: Role, _team: Team) {}
@@ -0,0 +1,5 @@
+pub mod models;
+pub mod services;
+pub mod grouped;
+pub mod sorted;
+pub mod multiline;
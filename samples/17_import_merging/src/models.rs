@@ -0,0 +1,4 @@
+pub struct User;
+pub struct Role;
+pub struct Team;
+pub struct Project;
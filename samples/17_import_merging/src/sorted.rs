@@ -0,0 +1,7 @@
+// A sorted block of single imports: each missing name goes where it sorts.
+// `User` and `Team` land in the same place and go in sorted order, though
+// `User` is used first — should be SUGGESTIONS (E0412)
+use crate::models::Role;
+use crate::services::Scheduler;
+
+pub fn plan(_user: User, _team: Team, _role: Role, _scheduler: Scheduler, _mailer: Mailer) {}
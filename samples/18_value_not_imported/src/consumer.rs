@@ -0,0 +1,30 @@
+// Values from another module used without importing them — should be
+// SUGGESTIONS (E0425) with an import fix
+pub fn capacity() -> usize {
+    clamp(MAX_USERS)
+}
+
+pub fn greet() -> &'static str {
+    GREETING
+}
+
+// A unit struct and a tuple struct's constructor are values too: E0425
+pub fn limits() -> (crate::limits::Unlimited, crate::limits::Limit) {
+    (Unlimited, Limit(3))
+}
+
+// Defined in two modules: E0425 listing both, no fix
+pub fn clean(input: &str) -> String {
+    normalize(input)
+}
+
+// Private to its module: E0425 saying it isn't visible, no fix
+pub fn check() -> bool {
+    internal_check()
+}
+
+// Locals and parameters shadow the crate's items of the same name — should be SILENT
+pub fn local_values(clamp: usize) -> usize {
+    let normalize = |n: usize| n.min(clamp);
+    normalize(clamp)
+}
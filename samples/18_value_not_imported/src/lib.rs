@@ -0,0 +1,4 @@
+pub mod limits;
+pub mod text;
+pub mod legacy;
+pub mod consumer;
@@ -0,0 +1,13 @@
+pub const MAX_USERS: usize = 100;
+pub static GREETING: &str = "hello";
+
+pub struct Unlimited;
+pub struct Limit(pub usize);
+
+pub fn clamp(value: usize) -> usize {
+    value.min(MAX_USERS)
+}
+
+fn internal_check() -> bool {
+    true
+}
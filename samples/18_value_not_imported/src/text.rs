@@ -0,0 +1,3 @@
+pub fn normalize(input: &str) -> String {
+    input.trim().to_lowercase()
+}
@@ -0,0 +1,25 @@
+// Plural of an existing name — should be an ERROR (E0432) suggesting `Entry`
+use crate::store::Entries;
+
+// Abbreviation of an existing name — should be an ERROR (E0432) suggesting `UserRepository`
+use crate::store::UserRepo;
+
+// Equally close to `Reader` and `Header` — should be an ERROR (E0432) listing
+// both, with no fix since neither is clearly meant
+use crate::store::Leader;
+
+use crate::store::Status;
+
+// Transposed letters in a variant — should be an ERROR (E0599) suggesting `Archived`
+pub fn archived() -> Status {
+    Status::Arhcived
+}
+
+// Nothing close enough — should be an ERROR (E0432) with no suggestion
+use crate::store::Transaction;
+
+// Typo in a type name that isn't defined anywhere — should be an ERROR (E0412)
+// suggesting the imported `Status`
+pub fn status(current: Statsu) -> bool {
+    matches!(current, Status::Active)
+}
@@ -0,0 +1,13 @@
+pub struct Entry {
+    pub key: String,
+}
+
+pub struct UserRepository;
+
+pub struct Reader;
+pub struct Header;
+
+pub enum Status {
+    Active,
+    Archived,
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::*;
+
+/// Default baseline file name, in the crate root.
+pub const DEFAULT_BASELINE: &str = "rustpeek-baseline.json";
+
+const BASELINE_VERSION: u32 = 1;
+
+/// A recorded set of known diagnostics that `check --baseline` won't report.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub entries: Vec<BaselineEntry>,
+}
+
+/// One known diagnostic. Identified by its fingerprint, not its line, so
+/// unrelated edits that shift code around don't invalidate the baseline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub fingerprint: String,
+    pub code: Option<String>,
+    /// Path relative to the crate root, with `/` separators
+    pub file: String,
+    pub message: String,
+    /// The diagnostic's source line with whitespace normalized
+    pub snippet: String,
+}
+
+/// The outcome of checking an analysis result against a baseline.
+pub struct BaselineComparison {
+    /// Diagnostics not in the baseline
+    pub new: AnalysisResult,
+    /// Baseline entries that no longer occur
+    pub fixed: Vec<BaselineEntry>,
+}
+
+/// Record every diagnostic of `result` as a baseline entry.
+pub fn from_result(crate_root: &Path, result: &AnalysisResult) -> Baseline {
+    let mut lines = SourceLines::default();
+    let mut entries: Vec<BaselineEntry> = result
+        .diagnostics
+        .iter()
+        .map(|diag| entry_for(crate_root, diag, &mut lines))
+        .collect();
+    entries.sort_by(|a, b| (&a.file, &a.code, &a.message).cmp(&(&b.file, &b.code, &b.message)));
+    Baseline {
+        version: BASELINE_VERSION,
+        entries,
+    }
+}
+
+pub fn load(path: &Path) -> Result<Baseline, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("could not read {}: {e}", path.display()))?;
+    let baseline: Baseline =
+        serde_json::from_str(&content).map_err(|e| format!("invalid baseline {}: {e}", path.display()))?;
+    if baseline.version != BASELINE_VERSION {
+        return Err(format!(
+            "baseline {} has version {}, expected {BASELINE_VERSION}",
+            path.display(),
+            baseline.version
+        ));
+    }
+    Ok(baseline)
+}
+
+pub fn write(path: &Path, baseline: &Baseline) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(baseline).map_err(|e| e.to_string())?;
+    std::fs::write(path, content + "\n").map_err(|e| format!("could not write {}: {e}", path.display()))
+}
+
+/// Split `result` into diagnostics that are new and baseline entries that are fixed.
+///
+/// Fingerprints are matched as a multiset: two identical known diagnostics
+/// only hide two occurrences. If `checked_files` is given, only entries for
+/// those files can count as fixed, since the rest weren't analyzed.
+pub fn compare(
+    baseline: &Baseline,
+    crate_root: &Path,
+    result: AnalysisResult,
+    checked_files: Option<&[PathBuf]>,
+) -> BaselineComparison {
+    let mut remaining: HashMap<&str, Vec<&BaselineEntry>> = HashMap::new();
+    for entry in &baseline.entries {
+        remaining.entry(entry.fingerprint.as_str()).or_default().push(entry);
+    }
+
+    let mut lines = SourceLines::default();
+    let mut new = Vec::new();
+    for diag in result.diagnostics {
+        let entry = entry_for(crate_root, &diag, &mut lines);
+        let known = remaining.get_mut(entry.fingerprint.as_str()).and_then(|entries| entries.pop());
+        if known.is_none() {
+            new.push(diag);
+        }
+    }
+
+    let checked: Option<Vec<String>> =
+        checked_files.map(|files| files.iter().map(|f| relative_path(crate_root, f)).collect());
+    let mut fixed: Vec<BaselineEntry> = remaining
+        .into_values()
+        .flatten()
+        .filter(|entry| checked.as_ref().is_none_or(|files| files.contains(&entry.file)))
+        .cloned()
+        .collect();
+    fixed.sort_by(|a, b| (&a.file, &a.message).cmp(&(&b.file, &b.message)));
+
+    BaselineComparison {
+        new: AnalysisResult { diagnostics: new },
+        fixed,
+    }
+}
+
+/// Stable fingerprint of a diagnostic: a hash of its code (or rule ID, if it
+/// has no code), crate-relative file, message and normalized source line.
+pub fn fingerprint(crate_root: &Path, diag: &Diagnostic, source_line: &str) -> String {
+    let key = format!(
+        "{}\0{}\0{}\0{}",
+        diag.error_code.as_deref().unwrap_or(&diag.rule),
+        relative_path(crate_root, diag.file()),
+        diag.message,
+        normalize_snippet(source_line),
+    );
+    format!("{:016x}", fnv1a(key.as_bytes()))
+}
+
+/// Fingerprints of every diagnostic in `result`, in order.
+pub fn fingerprints(crate_root: &Path, result: &AnalysisResult) -> Vec<String> {
+    let mut lines = SourceLines::default();
+    result
+        .diagnostics
+        .iter()
+        .map(|diag| entry_for(crate_root, diag, &mut lines).fingerprint)
+        .collect()
+}
+
+fn entry_for(crate_root: &Path, diag: &Diagnostic, lines: &mut SourceLines) -> BaselineEntry {
+    let source_line = lines.get(diag.file(), diag.line());
+    BaselineEntry {
+        fingerprint: fingerprint(crate_root, diag, &source_line),
+        code: diag.error_code.clone(),
+        file: relative_path(crate_root, diag.file()),
+        message: diag.message.clone(),
+        snippet: normalize_snippet(&source_line),
+    }
+}
+
+/// Path relative to the crate root with `/` separators, so baselines are portable.
+pub(crate) fn relative_path(crate_root: &Path, file: &Path) -> String {
+    let root = crate_root.canonicalize().unwrap_or_else(|_| crate_root.to_path_buf());
+    let file_abs = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+    let relative = file_abs
+        .strip_prefix(&root)
+        .or_else(|_| file.strip_prefix(crate_root))
+        .unwrap_or(file);
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+/// Collapse runs of whitespace so reindenting doesn't change the fingerprint.
+fn normalize_snippet(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 64-bit FNV-1a. Used instead of `DefaultHasher`, whose output may change between Rust releases.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Source lines of diagnostic files, read once per file.
+#[derive(Default)]
+struct SourceLines {
+    files: HashMap<PathBuf, Vec<String>>,
+}
+
+impl SourceLines {
+    fn get(&mut self, file: &Path, line: usize) -> String {
+        let lines = self.files.entry(file.to_path_buf()).or_insert_with(|| {
+            crate::read_file(file)
+                .map(|content| content.lines().map(String::from).collect())
+                .unwrap_or_default()
+        });
+        line.checked_sub(1)
+            .and_then(|idx| lines.get(idx))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
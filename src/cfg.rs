@@ -0,0 +1,111 @@
+use syn::punctuated::Punctuated;
+
+use crate::config::Config;
+
+/// Remove items whose `#[cfg(...)]` is provably disabled under `config`.
+///
+/// Only predicates the config has an opinion on are evaluated: `feature = "..."`
+/// when `features` is set, and other flags when `cfg` is set. Anything else is
+/// treated as unknown, and items with unknown predicates are kept.
+pub fn strip_disabled(ast: &mut syn::File, config: &Config) {
+    if config.features.is_none() && config.cfg.is_none() {
+        return;
+    }
+    strip_items(&mut ast.items, config);
+}
+
+fn strip_items(items: &mut Vec<syn::Item>, config: &Config) {
+    items.retain(|item| is_enabled(item_attrs(item), config) != Some(false));
+    for item in items {
+        if let syn::Item::Mod(m) = item
+            && let Some((_, content)) = &mut m.content
+        {
+            strip_items(content, config);
+        }
+    }
+}
+
+/// Evaluate every `#[cfg]` on an item. None means "can't tell".
+fn is_enabled(attrs: &[syn::Attribute], config: &Config) -> Option<bool> {
+    let mut result = Some(true);
+    for attr in attrs.iter().filter(|a| a.path().is_ident("cfg")) {
+        let value = attr.parse_args::<syn::Meta>().ok().and_then(|meta| eval(&meta, config));
+        result = and(result, value);
+    }
+    result
+}
+
+/// Three-valued evaluation of a cfg predicate.
+fn eval(meta: &syn::Meta, config: &Config) -> Option<bool> {
+    match meta {
+        syn::Meta::Path(path) => {
+            let name = path.get_ident()?.to_string();
+            config.cfg.as_ref().map(|cfg| cfg.contains(&name))
+        }
+        syn::Meta::NameValue(nv) => {
+            let name = nv.path.get_ident()?.to_string();
+            let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(value), .. }) = &nv.value else {
+                return None;
+            };
+            let value = value.value();
+            if name == "feature" {
+                config.features.as_ref().map(|features| features.contains(&value))
+            } else {
+                let flag = format!("{name}=\"{value}\"");
+                config.cfg.as_ref().map(|cfg| cfg.iter().any(|c| c.replace(' ', "") == flag))
+            }
+        }
+        syn::Meta::List(list) => {
+            let args = list
+                .parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)
+                .ok()?;
+            let mut values = args.iter().map(|m| eval(m, config));
+            if list.path.is_ident("all") {
+                values.fold(Some(true), and)
+            } else if list.path.is_ident("any") {
+                values.fold(Some(false), or)
+            } else if list.path.is_ident("not") {
+                if args.len() == 1 { values.next()?.map(|v| !v) } else { None }
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn and(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+fn or(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
+    }
+}
+
+pub(crate) fn item_attrs(item: &syn::Item) -> &[syn::Attribute] {
+    match item {
+        syn::Item::Const(i) => &i.attrs,
+        syn::Item::Enum(i) => &i.attrs,
+        syn::Item::ExternCrate(i) => &i.attrs,
+        syn::Item::Fn(i) => &i.attrs,
+        syn::Item::ForeignMod(i) => &i.attrs,
+        syn::Item::Impl(i) => &i.attrs,
+        syn::Item::Macro(i) => &i.attrs,
+        syn::Item::Mod(i) => &i.attrs,
+        syn::Item::Static(i) => &i.attrs,
+        syn::Item::Struct(i) => &i.attrs,
+        syn::Item::Trait(i) => &i.attrs,
+        syn::Item::TraitAlias(i) => &i.attrs,
+        syn::Item::Type(i) => &i.attrs,
+        syn::Item::Union(i) => &i.attrs,
+        syn::Item::Use(i) => &i.attrs,
+        _ => &[],
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde_json::{Value, json};
+
+use crate::baseline;
+use crate::types::*;
+
+/// GitHub Actions workflow commands for `result`, one per diagnostic, so
+/// findings show up inline on pull requests.
+///
+/// GitHub resolves annotation paths against the repository checkout, which is
+/// the working directory of a workflow step, so paths are made relative to it.
+pub fn github_annotations(result: &AnalysisResult) -> Vec<String> {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    result
+        .diagnostics
+        .iter()
+        .map(|diag| {
+            let command = match diag.severity {
+                Severity::Error => "error",
+                Severity::Suggestion => "warning",
+            };
+            let mut properties = vec![format!("file={}", escape_property(&baseline::relative_path(&cwd, diag.file())))];
+            // Line 0 means the whole file, which is annotated without a position
+            if diag.span.start.line > 0 {
+                properties.push(format!("line={}", diag.span.start.line));
+                properties.push(format!("endLine={}", diag.span.end.line));
+                properties.push(format!("col={}", diag.span.start.column));
+                if diag.span.end.line == diag.span.start.line {
+                    properties.push(format!("endColumn={}", diag.span.end.column));
+                }
+            }
+            let title = diag.error_code.as_deref().unwrap_or(&diag.rule);
+            properties.push(format!("title={}", escape_property(title)));
+
+            format!("::{command} {}::{}", properties.join(","), escape_data(&full_message(diag)))
+        })
+        .collect()
+}
+
+/// A GitLab Code Quality report for `result`.
+///
+/// Fingerprints are the baseline fingerprints, so an unchanged finding keeps
+/// its identity across pipelines. GitLab drops entries with a repeated
+/// fingerprint, so identical diagnostics get an occurrence suffix.
+pub fn gitlab_report(crate_root: &Path, result: &AnalysisResult) -> Value {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    let issues: Vec<Value> = result
+        .diagnostics
+        .iter()
+        .zip(baseline::fingerprints(crate_root, result))
+        .map(|(diag, fingerprint)| {
+            let occurrence = seen.entry(fingerprint.clone()).or_default();
+            *occurrence += 1;
+            let fingerprint = if *occurrence == 1 { fingerprint } else { format!("{fingerprint}-{occurrence}") };
+
+            json!({
+                "type": "issue",
+                "check_name": diag.rule,
+                "description": full_message(diag),
+                "categories": ["Bug Risk"],
+                "severity": match diag.severity {
+                    Severity::Error => "major",
+                    Severity::Suggestion => "minor",
+                },
+                "fingerprint": fingerprint,
+                "location": {
+                    "path": baseline::relative_path(&cwd, diag.file()),
+                    "lines": { "begin": diag.line().max(1), "end": diag.span.end.line.max(1) },
+                },
+            })
+        })
+        .collect();
+    Value::Array(issues)
+}
+
+/// The message with notes and help folded in, for formats that only take plain text.
+fn full_message(diag: &Diagnostic) -> String {
+    let mut message = diag.message.clone();
+    for note in &diag.notes {
+        message.push_str(&format!("\nnote: {note}"));
+    }
+    for help in &diag.help {
+        message.push_str(&format!("\nhelp: {help}"));
+    }
+    message
+}
+
+/// Escape the message of a workflow command.
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escape a `key=value` property of a workflow command.
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::types::{AnalysisResult, Diagnostic, Severity};
+
+/// Name of the standalone config file, looked up in the crate root.
+pub const CONFIG_FILE: &str = "rustpeek.toml";
+
+/// Project configuration, from `rustpeek.toml` or `[package.metadata.rustpeek]`.
+///
+/// ```toml
+/// enable = ["E0432", "E0433"]   # only run these checks (default: all)
+/// disable = ["E0599", "unused-suppression"]  # never report these
+/// exclude = ["src/generated/**"]
+/// fixable = ["not-imported"]    # checks whose fixes `rustpeek fix` may apply (default: all)
+/// features = ["serde"]          # enabled features, for `#[cfg(feature = "...")]`
+/// cfg = ["unix", 'target_os="linux"']
+///
+/// [severity]
+/// E0412 = "error"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// If set, only diagnostics with these rule IDs or codes are reported
+    pub enable: Option<Vec<String>>,
+    /// Rule IDs and codes that are never reported
+    pub disable: Vec<String>,
+    /// Severity overrides per rule ID or code
+    pub severity: HashMap<String, Severity>,
+    /// Glob patterns (relative to the crate root) of files that are never validated.
+    /// Excluded files are still indexed, so items defined there still resolve.
+    pub exclude: Vec<String>,
+    /// If set, `fix` only applies fixes for these rule IDs or codes
+    pub fixable: Option<Vec<String>>,
+    /// Enabled cargo features. If unset, `#[cfg(feature = ...)]` is never evaluated.
+    pub features: Option<Vec<String>>,
+    /// Enabled cfg flags (`unix`, `target_os="linux"`). If unset, other `#[cfg]`
+    /// predicates are never evaluated.
+    pub cfg: Option<Vec<String>>,
+}
+
+/// Shape of Cargo.toml we care about: just `[package.metadata.rustpeek]`.
+#[derive(Deserialize)]
+struct Manifest {
+    package: Option<ManifestPackage>,
+}
+
+#[derive(Deserialize)]
+struct ManifestPackage {
+    metadata: Option<ManifestMetadata>,
+}
+
+#[derive(Deserialize)]
+struct ManifestMetadata {
+    rustpeek: Option<Config>,
+}
+
+/// Load the crate's configuration.
+///
+/// `rustpeek.toml` in the crate root wins; otherwise `[package.metadata.rustpeek]`
+/// in Cargo.toml is used. No config at all gives the defaults.
+pub fn load(crate_root: &Path) -> Result<Config, String> {
+    let config_file = crate_root.join(CONFIG_FILE);
+    if config_file.exists() {
+        let content = std::fs::read_to_string(&config_file)
+            .map_err(|e| format!("could not read {}: {e}", config_file.display()))?;
+        return toml::from_str(&content).map_err(|e| format!("invalid {}: {e}", config_file.display()));
+    }
+
+    let manifest_file = crate_root.join("Cargo.toml");
+    let Ok(content) = std::fs::read_to_string(&manifest_file) else {
+        return Ok(Config::default());
+    };
+    let manifest: Manifest =
+        toml::from_str(&content).map_err(|e| format!("invalid {}: {e}", manifest_file.display()))?;
+    Ok(manifest
+        .package
+        .and_then(|p| p.metadata)
+        .and_then(|m| m.rustpeek)
+        .unwrap_or_default())
+}
+
+impl Config {
+    /// Layer `overrides` (e.g. from CLI flags) on top of this config.
+    /// Set options replace ours; lists of disabled codes and exclusions are added to ours.
+    pub fn merge(&mut self, overrides: &Config) {
+        if overrides.enable.is_some() {
+            self.enable = overrides.enable.clone();
+        }
+        self.disable.extend(overrides.disable.iter().cloned());
+        self.severity
+            .extend(overrides.severity.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self.exclude.extend(overrides.exclude.iter().cloned());
+        if overrides.fixable.is_some() {
+            self.fixable = overrides.fixable.clone();
+        }
+        if overrides.features.is_some() {
+            self.features = overrides.features.clone();
+        }
+        if overrides.cfg.is_some() {
+            self.cfg = overrides.cfg.clone();
+        }
+    }
+
+    /// Is a check with this rule ID or error code enabled? A code several
+    /// rules share is enabled while any of them is.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        let mut rules = crate::rules::RULES
+            .iter()
+            .filter(|r| r.id.eq_ignore_ascii_case(name) || r.code.is_some_and(|c| c.eq_ignore_ascii_case(name)))
+            .peekable();
+        if rules.peek().is_none() {
+            return self.enables(|listed| listed.eq_ignore_ascii_case(name));
+        }
+        rules.any(|rule| {
+            self.enables(|listed| {
+                listed.eq_ignore_ascii_case(rule.id) || rule.code.is_some_and(|c| listed.eq_ignore_ascii_case(c))
+            })
+        })
+    }
+
+    /// Whether `enable` and `disable` let through the check that `is_listed` matches.
+    fn enables(&self, is_listed: impl Fn(&str) -> bool) -> bool {
+        self.enable.as_ref().is_none_or(|names| names.iter().any(|n| is_listed(n)))
+            && !self.disable.iter().any(|n| is_listed(n))
+    }
+
+    /// May `fix` apply the fix of this diagnostic?
+    pub fn is_fixable(&self, diag: &Diagnostic) -> bool {
+        self.fixable.as_ref().is_none_or(|names| names.iter().any(|name| diag.is_named(name)))
+    }
+
+    /// Is this file excluded from validation?
+    pub fn is_excluded(&self, crate_root: &Path, file: &Path) -> bool {
+        let relative = file.strip_prefix(crate_root).unwrap_or(file);
+        self.exclude.iter().any(|pattern| {
+            glob::Pattern::new(pattern).is_ok_and(|p| p.matches_path(relative))
+        })
+    }
+
+    /// Drop disabled diagnostics, apply severity overrides, and strip fixes
+    /// that `fix` isn't allowed to apply.
+    pub fn apply(&self, result: &mut AnalysisResult) {
+        result.diagnostics.retain(|d| self.enables(|name| d.is_named(name)));
+        for diag in &mut result.diagnostics {
+            self.apply_to_diagnostic(diag);
+        }
+    }
+
+    /// A severity set for the rule ID wins over one set for the error code.
+    fn apply_to_diagnostic(&self, diag: &mut Diagnostic) {
+        let by_rule = self.severity.iter().find(|(name, _)| name.eq_ignore_ascii_case(&diag.rule));
+        if let Some((_, severity)) = by_rule.or_else(|| self.severity.iter().find(|(name, _)| diag.is_named(name))) {
+            diag.severity = severity.clone();
+        }
+        if !self.is_fixable(diag) {
+            diag.fix = None;
+        }
+    }
+}
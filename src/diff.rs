@@ -0,0 +1,135 @@
+/// Lines of context around each change, as in `diff -u` and git.
+const CONTEXT: usize = 3;
+
+/// Above this many line pairs the changed middle of two texts is shown as one
+/// replacement instead of being diffed line by line.
+const MAX_TABLE: usize = 4_000_000;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// A unified diff from `before` to `after`, with `---`/`+++` headers naming
+/// `old_path` and `new_path`. Empty if the texts are equal.
+pub fn unified_diff(old_path: &str, new_path: &str, before: &str, after: &str) -> String {
+    let old: Vec<&str> = before.split_inclusive('\n').collect();
+    let new: Vec<&str> = after.split_inclusive('\n').collect();
+    let ops = line_ops(&old, &new);
+    if ops.iter().all(|(op, _, _)| *op == Op::Equal) {
+        return String::new();
+    }
+
+    let mut out = format!("--- {old_path}\n+++ {new_path}\n");
+    for hunk in hunks(&ops) {
+        let ops = &ops[hunk];
+        let (old_start, new_start) = (ops[0].1, ops[0].2);
+        let old_len = ops.iter().filter(|(op, _, _)| *op != Op::Insert).count();
+        let new_len = ops.iter().filter(|(op, _, _)| *op != Op::Delete).count();
+        // An empty side is numbered by the line before it
+        let start = |start: usize, len: usize| if len == 0 { start } else { start + 1 };
+        out.push_str(&format!(
+            "@@ -{},{old_len} +{},{new_len} @@\n",
+            start(old_start, old_len),
+            start(new_start, new_len)
+        ));
+        for &(op, i, j) in ops {
+            let (marker, line) = match op {
+                Op::Equal => (' ', old[i]),
+                Op::Delete => ('-', old[i]),
+                Op::Insert => ('+', new[j]),
+            };
+            out.push(marker);
+            out.push_str(line.strip_suffix('\n').unwrap_or(line));
+            out.push('\n');
+            if !line.ends_with('\n') {
+                out.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+    out
+}
+
+/// The edit script turning `old` into `new`: each op with the index of its
+/// line in `old` and in `new` (the next line's index on the side it skips).
+fn line_ops(old: &[&str], new: &[&str]) -> Vec<(Op, usize, usize)> {
+    // Fixes touch few lines, so only the middle between the common prefix
+    // and suffix needs a real diff
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_mid, new_mid) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    let mut ops: Vec<(Op, usize, usize)> = (0..prefix).map(|i| (Op::Equal, i, i)).collect();
+    for (op, i, j) in middle_ops(old_mid, new_mid) {
+        ops.push((op, prefix + i, prefix + j));
+    }
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+    ops.extend((0..suffix).map(|k| (Op::Equal, old_end + k, new_end + k)));
+    ops
+}
+
+/// Longest-common-subsequence diff of two short line lists.
+fn middle_ops(old: &[&str], new: &[&str]) -> Vec<(Op, usize, usize)> {
+    let (n, m) = (old.len(), new.len());
+    if n * m > MAX_TABLE {
+        let deletes = (0..n).map(|i| (Op::Delete, i, 0));
+        return deletes.chain((0..m).map(|j| (Op::Insert, n, j))).collect();
+    }
+
+    // lcs[i][j]: length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut ops = Vec::new();
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            ops.push((Op::Equal, i, j));
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            ops.push((Op::Insert, i, j));
+            j += 1;
+        } else {
+            ops.push((Op::Delete, i, j));
+            i += 1;
+        }
+    }
+    // Show removed lines before the lines that replace them
+    for run in ops.split_mut(|(op, _, _)| *op == Op::Equal) {
+        run.sort_by_key(|(op, _, _)| *op == Op::Insert);
+    }
+    let (mut i, mut j) = (0, 0);
+    for (op, op_i, op_j) in &mut ops {
+        (*op_i, *op_j) = (i, j);
+        i += usize::from(*op != Op::Insert);
+        j += usize::from(*op != Op::Delete);
+    }
+    ops
+}
+
+/// Ranges of `ops` to print as hunks: each change with its context, with
+/// changes whose context touches merged into one hunk.
+fn hunks(ops: &[(Op, usize, usize)]) -> Vec<std::ops::Range<usize>> {
+    let mut hunks: Vec<std::ops::Range<usize>> = Vec::new();
+    for (k, _) in ops.iter().enumerate().filter(|(_, (op, _, _))| *op != Op::Equal) {
+        let start = k.saturating_sub(CONTEXT);
+        let end = (k + 1 + CONTEXT).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+    hunks
+}
@@ -64,6 +64,72 @@ pub fn discover_crate(root: &Path) -> CrateFiles {
     }
 }
 
+/// The result of matching user-supplied paths against a crate's files.
+#[derive(Debug, Default)]
+pub struct ChangedFiles {
+    /// Discovered source files selected by the inputs, as paths from [`CrateFiles`]
+    pub files: Vec<PathBuf>,
+    /// Inputs that didn't select any source file of the crate
+    pub unmatched: Vec<PathBuf>,
+}
+
+/// Match user-supplied paths against the crate's discovered files.
+///
+/// Paths are compared after canonicalization, so `src/foo.rs`, `./src/foo.rs`
+/// and absolute paths all select the same file. Relative paths are tried
+/// against the working directory first, then the crate root. A directory
+/// selects every source file below it, and a glob pattern (`src/**/*.rs`)
+/// selects every file it matches.
+pub fn match_changed_files(crate_files: &CrateFiles, inputs: &[PathBuf]) -> ChangedFiles {
+    let canonical: Vec<(PathBuf, &PathBuf)> = crate_files
+        .files
+        .values()
+        .filter_map(|path| Some((path.canonicalize().ok()?, path)))
+        .collect();
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let bases = [cwd.as_path(), crate_files.root.as_path()];
+
+    let mut result = ChangedFiles::default();
+    for input in inputs {
+        let before = result.files.len();
+        let input_str = input.to_string_lossy();
+
+        if input_str.contains(['*', '?', '[']) {
+            for base in bases {
+                let Some(base) = base.canonicalize().ok() else {
+                    continue;
+                };
+                let pattern = base.join(input);
+                let Ok(pattern) = glob::Pattern::new(&pattern.to_string_lossy()) else {
+                    continue;
+                };
+                for (canonical_path, original) in &canonical {
+                    if pattern.matches_path(canonical_path) && !result.files.contains(original) {
+                        result.files.push((*original).clone());
+                    }
+                }
+            }
+        } else if let Some(target) = bases.iter().find_map(|base| base.join(input).canonicalize().ok()) {
+            for (canonical_path, original) in &canonical {
+                let selected = if target.is_dir() {
+                    canonical_path.starts_with(&target)
+                } else {
+                    *canonical_path == target
+                };
+                if selected && !result.files.contains(original) {
+                    result.files.push((*original).clone());
+                }
+            }
+        }
+
+        if result.files.len() == before {
+            result.unmatched.push(input.clone());
+        }
+    }
+
+    result
+}
+
 /// Convert a file path relative to src/ into a module path.
 ///
 /// Examples:
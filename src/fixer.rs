@@ -1,123 +1,680 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use crate::types::{AnalysisResult, Fix};
+use serde::Serialize;
 
-/// Apply all fixes from an analysis result to the filesystem.
-/// Returns the number of fixes applied and the remaining (unfixable) diagnostics.
-pub fn apply_fixes(result: &AnalysisResult) -> ApplyResult {
-    // Group fixes by file, then sort by line descending so insertions
-    // don't shift line numbers for subsequent fixes in the same file.
-    let mut fixes_by_file: HashMap<PathBuf, Vec<&Fix>> = HashMap::new();
-    let mut applied = 0;
+use crate::config::Config;
+use crate::discovery::CrateFiles;
+use crate::journal::JournalEntry;
+use crate::types::{AnalysisResult, Applicability, Diagnostic, Fix, ModuleInfo, ModulePath, Severity, SymbolTable, TextEdit};
 
-    for diag in &result.diagnostics {
-        if let Some(fix) = &diag.fix {
-            let file = match fix {
-                Fix::InsertLine { file, .. } => file,
-                Fix::ReplaceLine { file, .. } => file,
-                Fix::RemoveLine { file, .. } => file,
-            };
-            fixes_by_file.entry(file.clone()).or_default().push(fix);
+/// The fixes of an analysis result, worked out in memory: what applying them
+/// would write, without touching any file.
+pub struct FixPlan {
+    /// The fixes to apply; duplicate, deferred and rejected fixes are left out
+    pub fixes: Vec<Fix>,
+    /// Fixes that were tried and left out because they didn't help
+    pub rejected: Vec<RejectedFix>,
+    /// Fixes that conflict with a fix applied before them, left for the next run
+    pub deferred: Vec<RejectedFix>,
+    /// Every file the fixes edit, sorted by path
+    pub files: Vec<FileChange>,
+    /// Files that couldn't be read; fixes that edit them are left out
+    pub errors: Vec<String>,
+    failed_files: HashSet<PathBuf>,
+    /// Fixes that weren't selected; their diagnostics remain, fix included
+    unselected: Vec<Fix>,
+}
+
+/// Which diagnostics `fix` may fix, by rule ID or error code, and whether
+/// fixes that may be incorrect are among them.
+#[derive(Debug, Clone, Default)]
+pub struct FixFilter {
+    /// If set, only diagnostics matching one of these are fixed
+    pub only: Option<Vec<String>>,
+    /// Diagnostics matching one of these are never fixed
+    pub exclude: Vec<String>,
+    /// Apply `maybe-incorrect` fixes too, not only `machine-applicable` ones
+    pub maybe_incorrect: bool,
+}
+
+impl FixFilter {
+    /// Whether the fix of `diag` may be applied.
+    pub fn allows(&self, diag: &Diagnostic) -> bool {
+        let applicable = diag
+            .fix
+            .as_ref()
+            .is_some_and(|fix| self.maybe_incorrect || fix.applicability == Applicability::MachineApplicable);
+        let matches = |names: &[String]| names.iter().any(|name| diag.is_named(name));
+        applicable && self.only.as_deref().is_none_or(matches) && !matches(&self.exclude)
+    }
+
+    /// An error naming the first entry that isn't a rule ID or error code rustpeek knows.
+    pub fn validate(&self) -> Result<(), String> {
+        let unknown = self.only.iter().flatten().chain(&self.exclude).find(|name| crate::rules::lookup(name).is_none());
+        match unknown {
+            Some(name) => Err(format!("unknown rule or error code `{name}`")),
+            None => Ok(()),
         }
     }
+}
 
-    for (file_path, mut fixes) in fixes_by_file {
-        let content = match std::fs::read_to_string(&file_path) {
-            Ok(c) => c,
-            Err(_) => continue,
-        };
+/// A fix that was left out, and why.
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectedFix {
+    pub fix: Fix,
+    pub reason: String,
+}
+
+/// A file's content before and after fixing.
+pub struct FileChange {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+}
+
+impl FileChange {
+    /// The change as a unified diff with git-style `a/`, `b/` paths relative to the crate root.
+    pub fn diff(&self, crate_root: &Path) -> String {
+        let path = crate::baseline::relative_path(crate_root, &self.path);
+        crate::diff::unified_diff(&format!("a/{path}"), &format!("b/{path}"), &self.before, &self.after)
+    }
+}
 
-        let mut lines: Vec<String> = content.lines().map(String::from).collect();
+impl FixPlan {
+    /// Whether a fix couldn't be applied because a file it edits failed.
+    fn failed(&self, fix: &Fix) -> bool {
+        fix.edits.iter().any(|edit| self.failed_files.contains(&edit.file))
+    }
+
+    /// The change a single planned fix makes to each file it edits, as if it
+    /// were the only fix applied.
+    pub fn preview(&self, fix: &Fix) -> Vec<FileChange> {
+        let edits = edits_by_file(&[fix]);
+        self.files
+            .iter()
+            .filter_map(|change| {
+                let edits = edits.get(change.path.as_path())?;
+                Some(FileChange {
+                    path: change.path.clone(),
+                    before: change.before.clone(),
+                    after: apply_edits(&change.before, edits),
+                })
+            })
+            .collect()
+    }
+
+    /// Diagnostics of `result` the plan doesn't fix. A rejected fix is taken
+    /// off its diagnostic, and a deferred one kept for the next run, each with
+    /// a note saying why.
+    pub fn remaining(&self, result: &AnalysisResult) -> AnalysisResult {
+        let find = |list: &'_ [RejectedFix], fix: &Fix| list.iter().position(|r| r.fix.edits == fix.edits);
+        let unselected = |fix: &Fix| self.unselected.iter().any(|other| other.edits == fix.edits);
+        AnalysisResult {
+            diagnostics: result
+                .diagnostics
+                .iter()
+                .filter(|d| {
+                    d.fix.as_ref().is_none_or(|fix| {
+                        self.failed(fix)
+                            || find(&self.rejected, fix).is_some()
+                            || find(&self.deferred, fix).is_some()
+                            || unselected(fix)
+                    })
+                })
+                .map(|d| {
+                    let Some(fix) = &d.fix else {
+                        return d.clone();
+                    };
+                    if let Some(i) = find(&self.rejected, fix) {
+                        let mut d = d.clone().with_note(format!("not auto-fixed: {}", self.rejected[i].reason));
+                        d.fix = None;
+                        d
+                    } else if let Some(i) = find(&self.deferred, fix) {
+                        d.clone().with_note(format!(
+                            "not auto-fixed: {}; run `rustpeek fix` again to re-check it",
+                            self.deferred[i].reason
+                        ))
+                    } else {
+                        d.clone()
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Work out the fixes of `result` in memory, keeping only those that verifiably
+/// help: each is applied to in-memory copies of the files, which are analyzed
+/// again with `config` (see [`verify`]). Only `machine-applicable` fixes are
+/// planned; the others are left for review.
+pub fn plan_fixes(crate_root: &Path, config: &Config, result: &AnalysisResult) -> FixPlan {
+    let filter = FixFilter::default();
+    plan_selected_fixes(crate_root, config, result, |d| filter.allows(d))
+}
 
-        // Sort fixes by line number descending so we can apply bottom-up
-        // without invalidating line indices
-        fixes.sort_by(|a, b| fix_line(b).cmp(&fix_line(a)));
+/// Like [`plan_fixes`], but only for the diagnostics `select` accepts. The
+/// others are left as they are, fix included, among the remaining diagnostics.
+pub fn plan_selected_fixes(
+    crate_root: &Path,
+    config: &Config,
+    result: &AnalysisResult,
+    mut select: impl FnMut(&Diagnostic) -> bool,
+) -> FixPlan {
+    let (chosen, unselected): (Vec<&Diagnostic>, Vec<&Diagnostic>) =
+        result.diagnostics.iter().filter(|d| d.fix.is_some()).partition(|d| select(d));
+    let unselected: Vec<Fix> = unselected.into_iter().filter_map(|d| d.fix.clone()).collect();
+    // A fix identical to an unselected one stays unapplied too
+    let chosen: Vec<&Diagnostic> = chosen
+        .into_iter()
+        .filter(|d| d.fix.as_ref().is_some_and(|fix| !unselected.iter().any(|other| other.edits == fix.edits)))
+        .collect();
+    let (selected, deferred) = select_fixes(&chosen);
 
-        // Deduplicate: remove InsertLine fixes if a ReplaceLine already
-        // corrects the import to that name, and dedup identical inserts
-        let mut seen_inserts = std::collections::HashSet::new();
-        let mut replace_targets = std::collections::HashSet::new();
-        for fix in &fixes {
-            if let Fix::ReplaceLine { new_text, .. } = fix {
-                replace_targets.insert(new_text.clone());
+    let mut contents: HashMap<PathBuf, String> = HashMap::new();
+    let mut errors = Vec::new();
+    let mut failed_files = HashSet::new();
+    for edit in selected.iter().flat_map(|d| fix_edits(d)) {
+        if contents.contains_key(&edit.file) || failed_files.contains(&edit.file) {
+            continue;
+        }
+        match std::fs::read_to_string(&edit.file) {
+            Ok(content) => {
+                contents.insert(edit.file.clone(), content);
+            }
+            Err(e) => {
+                errors.push(format!("could not read {}: {e}", edit.file.display()));
+                failed_files.insert(edit.file.clone());
             }
         }
-        fixes.retain(|fix| {
-            if let Fix::InsertLine { content, .. } = fix {
-                // Skip if this inserts a `use` for a name that a ReplaceLine already fixes
-                for target in &replace_targets {
-                    if content.contains(&format!("::{target};")) {
-                        return false;
-                    }
-                }
-                // Dedup identical inserts
-                return seen_inserts.insert(content.clone());
+    }
+
+    // A fix with an edit outside its file as read now, or splitting a character,
+    // was worked out for other content; it's rejected rather than applied in part
+    let (candidates, misfits): (Vec<&Diagnostic>, Vec<&Diagnostic>) = selected
+        .into_iter()
+        .filter(|d| fix_edits(d).iter().all(|edit| !failed_files.contains(&edit.file)))
+        .partition(|d| fix_edits(d).iter().all(|edit| edit_fits(&contents[&edit.file], edit)));
+    let Verified { fixes, mut rejected, before, after } = verify(crate_root, config, &contents, candidates);
+    rejected.extend(misfits.into_iter().map(|diag| RejectedFix {
+        fix: diag.fix.clone().expect("candidates have fixes"),
+        reason: "its edits don't fit the file as it is now".to_string(),
+    }));
+    // A conflicting fix is moot if the fixes applied resolve its diagnostic anyway
+    let deferred: Vec<RejectedFix> = deferred
+        .into_iter()
+        .filter(|(diag, _)| !resolves(diag, &before, &after))
+        .map(|(diag, reason)| RejectedFix {
+            fix: diag.fix.clone().expect("deferred diagnostics have fixes"),
+            reason,
+        })
+        .collect();
+
+    let mut files: Vec<FileChange> = edits_by_file(&fixes)
+        .into_iter()
+        .map(|(path, edits)| {
+            let before = contents.remove(path).unwrap_or_default();
+            FileChange {
+                path: path.to_path_buf(),
+                after: apply_edits(&before, &edits),
+                before,
             }
-            true
-        });
-
-        for fix in &fixes {
-            match fix {
-                Fix::InsertLine { line, content, .. } => {
-                    let idx = if *line == 0 {
-                        lines.len()
-                    } else {
-                        (*line - 1).min(lines.len())
-                    };
-                    // Insert each line of content (handle trailing newline)
-                    let insert_content = content.trim_end_matches('\n');
-                    lines.insert(idx, insert_content.to_string());
-                    applied += 1;
-                }
-                Fix::ReplaceLine { line, old_text, new_text, .. } => {
-                    let idx = line.saturating_sub(1);
-                    if idx < lines.len() && lines[idx].contains(old_text.as_str()) {
-                        lines[idx] = lines[idx].replace(old_text.as_str(), new_text);
-                        applied += 1;
-                    }
-                }
-                Fix::RemoveLine { line, .. } => {
-                    let idx = line.saturating_sub(1);
-                    if idx < lines.len() {
-                        lines.remove(idx);
-                        applied += 1;
-                    }
-                }
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    errors.sort();
+
+    FixPlan {
+        fixes: fixes.into_iter().cloned().collect(),
+        rejected,
+        deferred,
+        files,
+        errors,
+        failed_files,
+        unselected,
+    }
+}
+
+/// Candidate fixes split by [`verify`].
+struct Verified<'r> {
+    fixes: Vec<&'r Fix>,
+    rejected: Vec<RejectedFix>,
+    /// The edited files analyzed without any fix, and with `fixes`
+    before: AnalysisResult,
+    after: AnalysisResult,
+}
+
+/// Split candidate fixes into those that help and those that don't.
+///
+/// A fix helps if, once applied, the diagnostic it belongs to is gone, no
+/// diagnostic is reported that wasn't before, and the total goes down.
+/// Diagnostics are compared by code, file and message, not position, since
+/// fixes move code around. The edited files are analyzed along with those
+/// importing from them (see [`affected_files`]). All fixes are first checked
+/// together; if that shows anything new, they're added one at a time and
+/// each is kept only if it helps on top of those kept before it.
+fn verify<'r>(
+    crate_root: &Path,
+    config: &Config,
+    contents: &HashMap<PathBuf, String>,
+    candidates: Vec<&'r Diagnostic>,
+) -> Verified<'r> {
+    if candidates.is_empty() {
+        return Verified {
+            fixes: Vec::new(),
+            rejected: Vec::new(),
+            before: AnalysisResult { diagnostics: Vec::new() },
+            after: AnalysisResult { diagnostics: Vec::new() },
+        };
+    }
+
+    // The crate is discovered and indexed once; each analysis re-indexes only
+    // the edited modules
+    let crate_files = crate::discovery::discover_crate(crate_root);
+    let mut symbols = crate::build_symbol_table(&crate_files, contents, config);
+    let files = affected_files(crate_root, &crate_files, &symbols, contents);
+    let edited: Vec<(&ModulePath, &PathBuf)> =
+        crate_files.files.iter().filter(|(_, path)| contents.contains_key(*path)).collect();
+    let mut analyze = |diags: &[&Diagnostic]| -> AnalysisResult {
+        let fixes: Vec<&Fix> = diags.iter().filter_map(|d| d.fix.as_ref()).collect();
+        let edits = edits_by_file(&fixes);
+        let overlay: HashMap<PathBuf, String> = contents
+            .iter()
+            .map(|(path, content)| {
+                let edits = edits.get(path.as_path()).map(Vec::as_slice).unwrap_or_default();
+                (path.clone(), apply_edits(content, edits))
+            })
+            .collect();
+        let originals: Vec<(ModulePath, Option<ModuleInfo>)> = edited
+            .iter()
+            .map(|&(module, path)| {
+                let original = match crate::index_module(module, path, &overlay, config) {
+                    Some(info) => symbols.modules.insert(module.clone(), info),
+                    None => symbols.modules.remove(module),
+                };
+                (module.clone(), original)
+            })
+            .collect();
+        let result = crate::analyze_discovered(crate_root, &crate_files, Some(&files), &overlay, config, Some(&symbols));
+        for (module, original) in originals {
+            match original {
+                Some(info) => symbols.modules.insert(module, info),
+                None => symbols.modules.remove(&module),
+            };
+        }
+        result
+    };
+
+    let before = analyze(&[]);
+    let all = analyze(&candidates);
+    if new_diagnostic(&before, &all).is_none() && candidates.iter().all(|d| resolves(d, &before, &all)) {
+        return Verified {
+            fixes: candidates.iter().filter_map(|d| d.fix.as_ref()).collect(),
+            rejected: Vec::new(),
+            before,
+            after: all,
+        };
+    }
+
+    let mut kept: Vec<&Diagnostic> = Vec::new();
+    let mut rejected = Vec::new();
+    // The analysis with the fixes kept so far, if any
+    let mut kept_result: Option<AnalysisResult> = None;
+    for diag in candidates {
+        let mut trial = kept.clone();
+        trial.push(diag);
+        let after = analyze(&trial);
+
+        let current = kept_result.as_ref().unwrap_or(&before);
+        let new = new_diagnostic(current, &after);
+        let reason = match new {
+            Some(new) if new.severity == Severity::Error => Some(format!("it would introduce {}", describe(crate_root, new))),
+            _ if !resolves(diag, current, &after) => Some("the diagnostic would still be reported".to_string()),
+            Some(new) if after.diagnostics.len() >= current.diagnostics.len() => {
+                Some(format!("it would introduce {}", describe(crate_root, new)))
+            }
+            _ => None,
+        };
+        match reason {
+            Some(reason) => rejected.push(RejectedFix {
+                fix: diag.fix.clone().expect("candidates have fixes"),
+                reason,
+            }),
+            None => {
+                kept.push(diag);
+                kept_result = Some(after);
             }
         }
+    }
+    Verified {
+        fixes: kept.iter().filter_map(|d| d.fix.as_ref()).collect(),
+        rejected,
+        after: kept_result.unwrap_or_else(|| analyze(&[])),
+        before,
+    }
+}
+
+/// The edited files, and every file that imports from one of their modules,
+/// directly or through another such file: editing a module's `use` items can
+/// change what modules importing from it see.
+fn affected_files(
+    crate_root: &Path,
+    crate_files: &CrateFiles,
+    symbols: &SymbolTable,
+    contents: &HashMap<PathBuf, String>,
+) -> Vec<PathBuf> {
+    let crate_name = crate::read_crate_name(crate_root);
+
+    let mut affected: Vec<&ModulePath> =
+        crate_files.files.iter().filter(|(_, path)| contents.contains_key(*path)).map(|(module, _)| module).collect();
+    loop {
+        let imports_from_affected = |module: &ModulePath, info: &ModuleInfo| {
+            info.uses.iter().any(|u| {
+                crate::validator::resolve_use_path(&u.path, module, symbols, crate_name.as_deref())
+                    .is_some_and(|path| affected.iter().any(|a| path.starts_with(&a.0)))
+            })
+        };
+        let importers: Vec<&ModulePath> = symbols
+            .modules
+            .iter()
+            .filter(|(module, info)| !affected.contains(module) && imports_from_affected(module, info))
+            .map(|(module, _)| module)
+            .collect();
+        if importers.is_empty() {
+            break;
+        }
+        affected.extend(importers);
+    }
+
+    let mut files: Vec<PathBuf> = contents.keys().cloned().collect();
+    files.extend(affected.iter().filter_map(|module| crate_files.files.get(*module).cloned()));
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Identity of a diagnostic that survives edits elsewhere in its file.
+fn identity(diag: &Diagnostic) -> (&str, &Path, &str) {
+    (diag.error_code.as_deref().unwrap_or(&diag.rule), diag.file(), &diag.message)
+}
 
-        // Write back
-        let new_content = lines.join("\n") + "\n";
-        let _ = std::fs::write(&file_path, new_content);
+fn counts(result: &AnalysisResult) -> HashMap<(&str, &Path, &str), usize> {
+    let mut counts = HashMap::new();
+    for diag in &result.diagnostics {
+        *counts.entry(identity(diag)).or_default() += 1;
     }
+    counts
+}
 
-    let remaining = result
+/// A diagnostic of `after` that `before` didn't have, errors first.
+fn new_diagnostic<'a>(before: &AnalysisResult, after: &'a AnalysisResult) -> Option<&'a Diagnostic> {
+    let before = counts(before);
+    let mut seen: HashMap<(&str, &Path, &str), usize> = HashMap::new();
+    let mut new: Vec<&Diagnostic> = after
         .diagnostics
         .iter()
-        .filter(|d| d.fix.is_none())
-        .cloned()
+        .filter(|diag| {
+            let seen = seen.entry(identity(diag)).or_default();
+            *seen += 1;
+            *seen > before.get(&identity(diag)).copied().unwrap_or(0)
+        })
         .collect();
+    new.sort_by_key(|diag| diag.severity != Severity::Error);
+    new.into_iter().next()
+}
+
+/// Whether `diag` is reported fewer times in `after` than in `before`.
+fn resolves(diag: &Diagnostic, before: &AnalysisResult, after: &AnalysisResult) -> bool {
+    let key = identity(diag);
+    counts(after).get(&key).copied().unwrap_or(0) < counts(before).get(&key).copied().unwrap_or(0)
+}
+
+/// A short description of a diagnostic for a rejection reason.
+fn describe(crate_root: &Path, diag: &Diagnostic) -> String {
+    let severity = match diag.severity {
+        Severity::Error => "error",
+        Severity::Suggestion => "suggestion",
+    };
+    let code = diag.error_code.as_deref().map(|c| format!("[{c}]")).unwrap_or_default();
+    format!(
+        "{severity}{code} at {}:{}: {}",
+        crate::baseline::relative_path(crate_root, diag.file()),
+        diag.line(),
+        diag.message
+    )
+}
+
+fn fix_edits(diag: &Diagnostic) -> &[TextEdit] {
+    diag.fix.as_ref().map(|fix| fix.edits.as_slice()).unwrap_or_default()
+}
+
+/// The edits of `fixes` grouped by file.
+fn edits_by_file<'f>(fixes: &[&'f Fix]) -> HashMap<&'f Path, Vec<&'f TextEdit>> {
+    let mut by_file: HashMap<&Path, Vec<&TextEdit>> = HashMap::new();
+    for edit in fixes.iter().flat_map(|fix| &fix.edits) {
+        by_file.entry(&edit.file).or_default().push(edit);
+    }
+    by_file
+}
+
+/// Apply the fixes from an analysis result to the filesystem (see [`plan_fixes`]).
+/// Returns the fixes applied, files that couldn't be rewritten and the
+/// remaining (unfixed) diagnostics.
+pub fn apply_fixes(crate_root: &Path, config: &Config, result: &AnalysisResult) -> ApplyResult {
+    apply_plan(crate_root, plan_fixes(crate_root, config, result), result)
+}
+
+/// Write the files of a plan made from `result`, recording the run in the journal.
+pub fn apply_plan(crate_root: &Path, mut plan: FixPlan, result: &AnalysisResult) -> ApplyResult {
+    // Record the originals before touching anything, so the run can be undone;
+    // without a journal entry nothing is written
+    let mut entry = JournalEntry::new(crate_root, &plan.files);
+    let journal = if plan.files.is_empty() {
+        None
+    } else {
+        match entry.record(crate_root) {
+            Ok(path) => Some(path),
+            Err(e) => {
+                plan.errors.push(format!("{e}; no fixes were applied"));
+                plan.failed_files.extend(plan.files.iter().map(|change| change.path.clone()));
+                plan.files.clear();
+                None
+            }
+        }
+    };
+
+    for change in &plan.files {
+        if let Err(e) = write_atomic(&change.path, &change.after) {
+            plan.errors.push(format!("could not write {}: {e}", change.path.display()));
+            plan.failed_files.insert(change.path.clone());
+        }
+    }
+
+    // Files that weren't written have nothing to undo. Entry files are in the
+    // same order as the plan's.
+    let journal = journal.and_then(|path| {
+        let mut written = plan.files.iter().map(|change| !plan.failed_files.contains(&change.path));
+        entry.files.retain(|_| written.next().unwrap_or(false));
+        if entry.files.is_empty() {
+            let _ = std::fs::remove_file(&path);
+            return None;
+        }
+        entry.save(&path).ok().map(|()| path)
+    });
+
+    // A fix counts as applied only if every file it edits was written
+    let remaining = plan.remaining(result);
+    let applied: Vec<Fix> = plan.fixes.iter().filter(|fix| !plan.failed(fix)).cloned().collect();
+    let failed_files = &plan.failed_files;
+    plan.files.retain(|change| !failed_files.contains(&change.path));
 
     ApplyResult {
-        fixes_applied: applied,
-        remaining: AnalysisResult {
-            diagnostics: remaining,
-        },
+        fixes_applied: applied.len(),
+        applied,
+        rejected: plan.rejected,
+        deferred: plan.deferred,
+        write_errors: plan.errors,
+        journal,
+        files: plan.files,
+        remaining,
     }
 }
 
-/// Result of applying fixes
-pub struct ApplyResult {
-    pub fixes_applied: usize,
-    pub remaining: AnalysisResult,
+/// The text of a file after applying `edits` to its current `content`.
+///
+/// Edit offsets don't count a byte order mark (see [`crate::read_file`]), so
+/// one is set aside and restored. Inserted line breaks follow the file's line
+/// endings, and whether the file ends with a newline is kept as it was.
+///
+/// # Panics
+///
+/// If an edit doesn't fit `content` (see [`edit_fits`]); fixes with such
+/// edits are left out before applying any.
+pub fn apply_edits(content: &str, edits: &[&TextEdit]) -> String {
+    let (bom, text) = match content.strip_prefix(crate::BOM) {
+        Some(rest) => (crate::BOM, rest),
+        None => ("", content),
+    };
+    let crlf = text.find('\n').is_some_and(|i| text[..i].ends_with('\r'));
+    let had_final_newline = text.ends_with('\n');
+
+    // Line insertions at one offset, such as imports added by several fixes,
+    // form one block: blank lines each puts around itself are kept at the ends.
+    // Imports inserted at one offset go in sorted order, each having been
+    // placed among the existing ones on its own.
+    let mut replacements: Vec<&str> = edits.iter().map(|edit| edit.replacement.as_str()).collect();
+    let mut in_order: Vec<usize> = (0..edits.len()).collect();
+    in_order.sort_by_cached_key(|&i| {
+        let import = edits[i].byte_range.is_empty().then(|| crate::imports::inserted_import(replacements[i])).flatten();
+        (edits[i].byte_range.start, import, i)
+    });
+    for pair in in_order.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let same_insertion_point = edits[a].byte_range.is_empty()
+            && edits[b].byte_range.is_empty()
+            && edits[a].byte_range.start == edits[b].byte_range.start;
+        if same_insertion_point && replacements[a].ends_with('\n') && replacements[b].ends_with('\n') {
+            replacements[a] = &replacements[a][..replacements[a].trim_end_matches('\n').len() + 1];
+            replacements[b] = replacements[b].trim_start_matches('\n');
+        }
+    }
+
+    // Apply bottom-up so earlier byte offsets stay valid. Insertions at the
+    // same offset are applied last-first, which keeps them in fix order.
+    let mut text = text.to_string();
+    for &i in in_order.iter().rev() {
+        let range = edits[i].byte_range.clone();
+        if crlf {
+            text.replace_range(range, &replacements[i].replace("\r\n", "\n").replace('\n', "\r\n"));
+        } else {
+            text.replace_range(range, replacements[i]);
+        }
+    }
+
+    let line_ending = if crlf { "\r\n" } else { "\n" };
+    if had_final_newline && !text.is_empty() && !text.ends_with('\n') {
+        text.push_str(line_ending);
+    } else if !had_final_newline && text.ends_with('\n') {
+        text.truncate(text.len() - 1);
+        if text.ends_with('\r') {
+            text.truncate(text.len() - 1);
+        }
+    }
+
+    format!("{bom}{text}")
+}
+
+/// Replace `path` with `content` by writing a temporary file next to it and
+/// renaming it over the original, so the file is never left half-written.
+pub(crate) fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let temp = path.with_file_name(format!(".{name}.rustpeek-tmp"));
+
+    let written = std::fs::write(&temp, content)
+        .and_then(|()| std::fs::set_permissions(&temp, std::fs::metadata(path)?.permissions()))
+        .and_then(|()| std::fs::rename(&temp, path));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    written
 }
 
-fn fix_line(fix: &Fix) -> usize {
-    match fix {
-        Fix::InsertLine { line, .. } => *line,
-        Fix::ReplaceLine { line, .. } => *line,
-        Fix::RemoveLine { line, .. } => *line,
+/// Whether `edit` lies within `content`, past any byte order mark, and
+/// starts and ends between characters.
+pub fn edit_fits(content: &str, edit: &TextEdit) -> bool {
+    let text = content.strip_prefix(crate::BOM).unwrap_or(content);
+    edit.byte_range.start <= edit.byte_range.end && text.get(edit.byte_range.clone()).is_some()
+}
+
+/// Split diagnostics (with fixes) into those whose fixes can be applied
+/// together and those whose fixes conflict with one of them.
+///
+/// Identical fixes are applied once. A fix conflicts with an accepted one if
+/// any of their edits overlap, or if both bring the same name into scope in
+/// the same file. Fixes that change existing code are considered before pure
+/// insertions, so an import isn't added for a name a corrected import already
+/// brings in; otherwise fixes in files earlier by path, then earlier
+/// diagnostics of a file, win. Files are analyzed in no particular order, so
+/// the order of `diagnostics` across files doesn't matter.
+fn select_fixes<'r>(diagnostics: &[&'r Diagnostic]) -> (Vec<&'r Diagnostic>, Vec<(&'r Diagnostic, String)>) {
+    let index = |diag: &Diagnostic| diagnostics.iter().position(|other| std::ptr::eq(*other, diag));
+    let file = |diag: &'r Diagnostic| diag.fix.as_ref().map(Fix::file);
+    let mut ordered: Vec<&Diagnostic> = diagnostics.iter().copied().filter(|d| d.fix.is_some()).collect();
+    ordered.sort_by_key(|d| (fix_edits(d).iter().all(|edit| edit.byte_range.is_empty()), file(d), index(d)));
+
+    let mut accepted: Vec<&Diagnostic> = Vec::new();
+    let mut deferred = Vec::new();
+    for diag in ordered {
+        let fix = diag.fix.as_ref().expect("only diagnostics with fixes");
+        if accepted.iter().any(|other| fix_edits(other) == fix.edits) {
+            continue;
+        }
+        match accepted.iter().filter_map(|other| other.fix.as_ref()).find_map(|other| conflict(fix, other)) {
+            Some(reason) => deferred.push((diag, reason)),
+            None => accepted.push(diag),
+        }
+    }
+    // Apply, and report, by file and in diagnostic order
+    accepted.sort_by_key(|d| (file(d), index(d)));
+    (accepted, deferred)
+}
+
+/// Why `fix` can't be applied together with `other`, if it can't.
+fn conflict(fix: &Fix, other: &Fix) -> Option<String> {
+    if fix.edits.iter().any(|edit| other.edits.iter().any(|o| overlap(edit, o))) {
+        return Some(format!("it edits the same code as the fix to {}", other.message));
     }
+    match (&fix.binds, &other.binds) {
+        (Some(name), Some(other_name)) if name == other_name && fix.file() == other.file() => Some(format!(
+            "the fix to {} also brings `{name}` into scope",
+            other.message
+        )),
+        _ => None,
+    }
+}
+
+/// Whether two edits touch the same text. Insertions at the same offset don't.
+fn overlap(a: &TextEdit, b: &TextEdit) -> bool {
+    a.file == b.file
+        && a.byte_range.start < b.byte_range.end.max(b.byte_range.start + 1)
+        && b.byte_range.start < a.byte_range.end.max(a.byte_range.start + 1)
+        && !(a.byte_range.is_empty() && b.byte_range.is_empty())
 }
 
+/// Result of applying fixes
+pub struct ApplyResult {
+    pub fixes_applied: usize,
+    /// The fixes that were applied; duplicate, deferred and rejected fixes are left out
+    pub applied: Vec<Fix>,
+    /// Fixes left out because re-analysis showed they didn't help
+    pub rejected: Vec<RejectedFix>,
+    /// Fixes left for the next run because they conflict with an applied one
+    pub deferred: Vec<RejectedFix>,
+    /// Files that couldn't be read or written; their fixes weren't applied
+    pub write_errors: Vec<String>,
+    /// The journal entry `fix --undo` reverts, if any file was written
+    pub journal: Option<PathBuf>,
+    /// Files that were rewritten; `remaining` refers to their content before
+    pub files: Vec<FileChange>,
+    pub remaining: AnalysisResult,
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Which set of modified files to ask git for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitSelection {
+    /// Files that differ between a revision and the working tree, plus untracked files
+    ChangedSince(String),
+    /// Files staged in the index
+    Staged,
+    /// Files modified in the working tree but not staged, plus untracked files
+    Unstaged,
+}
+
+/// List the `.rs` files under `crate_root` selected by `selection`.
+/// Deleted files are excluded. Returned paths are `crate_root`-joined.
+pub fn changed_files(crate_root: &Path, selection: &GitSelection) -> Result<Vec<PathBuf>, String> {
+    let diff_args: Vec<&str> = match selection {
+        GitSelection::ChangedSince(rev) => vec!["diff", "--name-only", "--relative", "--diff-filter=ACMR", rev, "--"],
+        GitSelection::Staged => vec!["diff", "--name-only", "--relative", "--diff-filter=ACMR", "--cached", "--"],
+        GitSelection::Unstaged => vec!["diff", "--name-only", "--relative", "--diff-filter=ACMR", "--"],
+    };
+
+    let mut names = run_git(crate_root, &diff_args)?;
+    if *selection != GitSelection::Staged {
+        names.extend(run_git(crate_root, &["ls-files", "--others", "--exclude-standard"])?);
+    }
+
+    let mut files: Vec<PathBuf> = names
+        .into_iter()
+        .filter(|name| name.ends_with(".rs"))
+        .map(|name| crate_root.join(name))
+        .collect();
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Read the staged (index) contents of the given files, for checking exactly
+/// what is about to be committed rather than the working tree.
+pub fn staged_contents(crate_root: &Path, files: &[PathBuf]) -> Result<HashMap<PathBuf, String>, String> {
+    let mut contents = HashMap::new();
+    for file in files {
+        let relative = file.strip_prefix(crate_root).unwrap_or(file);
+        let spec = format!(":./{}", relative.to_string_lossy().replace('\\', "/"));
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(crate_root)
+            .args(["show", &spec])
+            .output()
+            .map_err(|e| format!("could not run git: {e}"))?;
+        if output.status.success() {
+            contents.insert(file.clone(), String::from_utf8_lossy(&output.stdout).into_owned());
+        }
+    }
+    Ok(contents)
+}
+
+/// Run a git command in `dir` and return its non-empty output lines.
+fn run_git(dir: &Path, args: &[&str]) -> Result<Vec<String>, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("could not run git: {e}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("`git {}` failed: {}", args.join(" "), stderr.trim()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect())
+}
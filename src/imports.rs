@@ -0,0 +1,520 @@
+use std::ops::Range;
+use std::path::Path;
+
+use quote::ToTokens;
+use syn::spanned::Spanned;
+
+use crate::types::{ItemInfo, ItemKind, ModulePath, SymbolTable, TextEdit, Vis};
+use crate::validator::resolve_use_path;
+
+/// Which block of imports a `use` belongs in, in the usual order:
+/// the standard library, other crates, then the crate itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Origin {
+    Std,
+    External,
+    Local,
+}
+
+fn origin(first_segment: &str, crate_name: Option<&str>) -> Origin {
+    match first_segment {
+        "std" | "core" | "alloc" => Origin::Std,
+        "crate" | "self" | "super" => Origin::Local,
+        _ if Some(first_segment) == crate_name => Origin::Local,
+        _ => Origin::External,
+    }
+}
+
+/// How a list of imports is sorted: by plain string order, as rustfmt does
+/// since style edition 2024, or with lowercase names before capitalized ones
+/// in each segment, as it did before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Order {
+    Ascii,
+    LowercaseFirst,
+}
+
+impl Order {
+    /// The order `keys` are sorted in, if any. A list sorted both ways is
+    /// taken to be in string order.
+    fn of<'k>(keys: impl Iterator<Item = &'k str> + Clone) -> Option<Order> {
+        [Order::Ascii, Order::LowercaseFirst].into_iter().find(|order| {
+            let mut sort_keys = keys.clone().map(|key| order.sort_key(key));
+            let Some(mut prev) = sort_keys.next() else {
+                return true;
+            };
+            sort_keys.all(|key| {
+                let sorted = prev <= key;
+                prev = key;
+                sorted
+            })
+        })
+    }
+
+    /// A comparable key for an import path like `crate::models::User`.
+    /// `self` sorts first either way.
+    fn sort_key(self, path: &str) -> Vec<(u8, String)> {
+        path.split("::")
+            .map(|segment| {
+                let rank = match self {
+                    _ if segment == "self" => 0,
+                    Order::Ascii => 1,
+                    Order::LowercaseFirst if segment.starts_with(char::is_uppercase) => 2,
+                    Order::LowercaseFirst => 1,
+                };
+                (rank, segment.to_string())
+            })
+            .collect()
+    }
+}
+
+/// The items an import goes among: the top level of a file, or the body of
+/// an inline module in it.
+struct Scope<'a> {
+    attrs: &'a [syn::Attribute],
+    items: &'a [syn::Item],
+    /// Where its content starts: the start of the file, or just past the `{`
+    start: usize,
+}
+
+impl<'a> Scope<'a> {
+    /// The body of the inline module `inline` leads to from the top of `ast`,
+    /// one module name at a time, or the file itself if `inline` is empty.
+    fn find(ast: &'a syn::File, inline: &[String]) -> Scope<'a> {
+        let mut scope = Scope { attrs: &ast.attrs, items: &ast.items, start: 0 };
+        for name in inline {
+            let module = scope.items.iter().find_map(|item| match item {
+                syn::Item::Mod(m) if m.ident == name => m.content.as_ref().map(|(brace, items)| (m, brace, items)),
+                _ => None,
+            });
+            let Some((module, brace, items)) = module else {
+                break;
+            };
+            scope = Scope { attrs: &module.attrs, items, start: brace.span.open().byte_range().end };
+        }
+        scope
+    }
+
+    /// The whitespace the scope's items are indented with.
+    fn indentation<'s>(&self, source: &'s str) -> &'s str {
+        self.items.first().map_or("", |item| indentation(source, item.span().byte_range().start))
+    }
+}
+
+/// A `use` item among the items of a [`Scope`].
+struct UseItem<'a> {
+    item: &'a syn::ItemUse,
+    /// From its first attribute to the `;`
+    range: Range<usize>,
+    /// The imported tree without whitespace, e.g. `crate::models::{Role,Team}`
+    key: String,
+    origin: Origin,
+}
+
+/// The module to import `item` from into module `from`, as written in a
+/// `use` (`crate::db`, `super`, `self::models`).
+///
+/// This is the shortest path visible from `from`, counting `pub use`
+/// re-exports of the item under its own name. A path relative to `from` is
+/// used when it's shorter than the one from `crate::`. None if the item
+/// can't be named from `from` at all.
+pub fn import_path(symbols: &SymbolTable, item: &ItemInfo, from: &ModulePath, crate_name: Option<&str>) -> Option<String> {
+    exported_from(symbols, item, crate_name)
+        .into_iter()
+        .filter(|(module, vis)| module != from && visible(vis, module, from))
+        .flat_map(|(module, _)| spellings(symbols, &module, from))
+        .min_by_key(|path| (path.len(), path[0] != "crate", path.clone()))
+        .map(|path| path.join("::"))
+}
+
+/// How many of the modules next to `from` (with the same parent) already
+/// import `item`, by name or with a glob of a module that exports it.
+pub fn sibling_imports(symbols: &SymbolTable, item: &ItemInfo, from: &ModulePath, crate_name: Option<&str>) -> usize {
+    let Some(parent) = from.parent() else {
+        return 0;
+    };
+    let sources = exported_from(symbols, item, crate_name);
+    symbols
+        .modules
+        .iter()
+        .filter(|(module, _)| *module != from && module.parent().as_ref() == Some(&parent))
+        .filter(|(module, info)| {
+            info.uses.iter().any(|u| {
+                let Some(resolved) = resolve_use_path(&u.path, module, symbols, crate_name) else {
+                    return false;
+                };
+                sources.iter().any(|(source, _)| {
+                    if u.is_glob {
+                        resolved == source.0
+                    } else {
+                        resolved.split_last().is_some_and(|(last, rest)| *last == item.name && rest == source.0)
+                    }
+                })
+            })
+        })
+        .count()
+}
+
+/// Modules the item can be imported from under its own name, with the
+/// visibility it has there: where it's defined, and every module that
+/// re-exports it, directly or through other re-exports. A `macro_rules!`
+/// macro is only at the crate root, if it's `#[macro_export]`ed, and
+/// wherever it's re-exported with `use`.
+fn exported_from(symbols: &SymbolTable, item: &ItemInfo, crate_name: Option<&str>) -> Vec<(ModulePath, Vis)> {
+    let mut found = vec![(item.module.clone(), item.vis.clone())];
+    if item.kind == ItemKind::Macro {
+        found = macro_paths(symbols, item, crate_name);
+    }
+    let mut modules: Vec<(&ModulePath, _)> = symbols.modules.iter().collect();
+    modules.sort_by(|a, b| a.0.0.cmp(&b.0.0));
+
+    let mut next = 0;
+    while next < found.len() {
+        let source = found[next].0.clone();
+        next += 1;
+        for (module, info) in &modules {
+            for u in &info.uses {
+                let Some(resolved) = resolve_use_path(&u.path, module, symbols, crate_name) else {
+                    continue;
+                };
+                let reexports = if u.is_glob {
+                    resolved == source.0
+                } else {
+                    u.alias == item.name && resolved.split_last().is_some_and(|(last, parent)| *last == item.name && parent == source.0)
+                };
+                if reexports && !found.iter().any(|(m, _)| m == *module) {
+                    found.push(((*module).clone(), u.vis.clone()));
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Where a `macro_rules!` macro can be named by path: at the crate root if
+/// it's exported, and where it's defined if a `use` there gives it a path.
+fn macro_paths(symbols: &SymbolTable, item: &ItemInfo, crate_name: Option<&str>) -> Vec<(ModulePath, Vis)> {
+    let mut found = Vec::new();
+    if item.vis == Vis::Public {
+        found.push((ModulePath::root(), Vis::Public));
+    }
+    let uses = symbols.modules.get(&item.module).map(|info| info.uses.as_slice()).unwrap_or_default();
+    let local_use = uses.iter().find(|u| {
+        !u.is_glob
+            && u.alias == item.name
+            && (u.path == [item.name.as_str()]
+                || resolve_use_path(&u.path, &item.module, symbols, crate_name)
+                    .is_some_and(|resolved| resolved.split_last() == Some((&item.name, &item.module.0[..]))))
+    });
+    if let Some(u) = local_use {
+        found.push((item.module.clone(), u.vis.clone()));
+    }
+    found
+}
+
+/// Ways to write `module` in a `use` in `from` that only pass through
+/// modules visible from `from`: from `crate`, and relative to `from`.
+fn spellings(symbols: &SymbolTable, module: &ModulePath, from: &ModulePath) -> Vec<Vec<String>> {
+    let mut spellings = Vec::new();
+    if modules_visible(symbols, &module.0[..1], &module.0[1..], from) {
+        spellings.push(module.0.clone());
+    }
+
+    let common = module.0.iter().zip(&from.0).take_while(|(a, b)| a == b).count();
+    if common > 0 && modules_visible(symbols, &module.0[..common], &module.0[common..], from) {
+        let ups = from.0.len() - common;
+        let mut relative = if ups == 0 { vec!["self".to_string()] } else { vec!["super".to_string(); ups] };
+        relative.extend(module.0[common..].iter().cloned());
+        spellings.push(relative);
+    }
+    spellings
+}
+
+/// Whether each of the modules `path`, nested below `base`, is visible from `from`.
+fn modules_visible(symbols: &SymbolTable, base: &[String], path: &[String], from: &ModulePath) -> bool {
+    let mut current = ModulePath(base.to_vec());
+    for segment in path {
+        let module = symbols.modules.get(&current).and_then(|info| {
+            info.items.iter().find(|item| item.kind == ItemKind::Module && item.name == *segment)
+        });
+        if !module.is_some_and(|module| visible(&module.vis, &current, from)) {
+            return false;
+        }
+        current = current.child(segment);
+    }
+    true
+}
+
+/// Whether something declared in `module` with visibility `vis` can be named from `from`.
+fn visible(vis: &Vis, module: &ModulePath, from: &ModulePath) -> bool {
+    match vis {
+        Vis::Public | Vis::PubCrate => true,
+        Vis::PubSuper => module.parent().is_some_and(|parent| from.0.starts_with(&parent.0)),
+        Vis::Private => from.0.starts_with(&module.0),
+    }
+}
+
+/// An edit that imports `name` from `module` (e.g. `crate::models`) into a
+/// file, or into the inline module `inline` names (see [`Scope::find`]), in
+/// the file's own style:
+///
+/// - into an existing `use module::{...}` group, in sorted position if the
+///   group is sorted;
+/// - else as a new line in the block of `use` lines (separated by blank
+///   lines) for the crate's own paths, in sorted position if the block is
+///   sorted, or as a new block where it belongs among the std, external and
+///   crate blocks;
+/// - else above the first item, below inner attributes and `//!` docs and
+///   above any comment directly attached to the item.
+///
+/// Imports of one run that land in the same place are applied in sorted
+/// order (see [`inserted_import`]). `source` is the file's text and `ast`
+/// its syntax tree; offsets are byte offsets into `source`.
+pub fn import_edit(
+    file: &Path,
+    source: &str,
+    ast: &syn::File,
+    inline: &[String],
+    module: &str,
+    name: &str,
+    crate_name: Option<&str>,
+) -> TextEdit {
+    let (at, replacement) = placement(source, &Scope::find(ast, inline), module, name, crate_name);
+    TextEdit {
+        file: file.to_path_buf(),
+        byte_range: at..at,
+        replacement,
+    }
+}
+
+fn placement(source: &str, scope: &Scope, module: &str, name: &str, crate_name: Option<&str>) -> (usize, String) {
+    let uses = use_items(scope.items, crate_name);
+    let segments: Vec<&str> = module.split("::").collect();
+
+    // Merge into `use module::{...};`. A `pub use`, or one with attributes
+    // such as `#[cfg]`, means something else, so only plain imports qualify.
+    for u in &uses {
+        if !u.item.attrs.is_empty() || !matches!(u.item.vis, syn::Visibility::Inherited) {
+            continue;
+        }
+        if let Some(group) = group_at(&u.item.tree, &segments) {
+            return merge_into_group(source, group, name);
+        }
+    }
+
+    let line = format!("{}use {module}::{name};", scope.indentation(source));
+    let key = format!("{module}::{name}");
+    let ours = origin(segments[0], crate_name);
+
+    let blocks = blocks(source, scope.items, &uses);
+    let Some(last_block) = blocks.last() else {
+        return above_first_item(source, scope, &line);
+    };
+    let block = blocks
+        .iter()
+        .find(|block| block.iter().any(|u| u.origin == ours && matches!(u.item.vis, syn::Visibility::Inherited)));
+    let Some(block) = block else {
+        // A block of its own, before the first block of a later origin
+        // (with the comments heading it), or else after the others
+        if let Some(later) = blocks.iter().find(|block| block[0].origin > ours) {
+            let at = above_comments(source, line_start(source, later[0].range.start), scope.start);
+            return (at, format!("{line}\n\n"));
+        }
+        let (at, separator) = line_end(source, last_block[last_block.len() - 1].range.end);
+        let followed = source[at..].lines().next().is_some_and(|next| !next.trim().is_empty());
+        return (at, format!("{separator}\n{line}\n{}", if followed { "\n" } else { "" }));
+    };
+
+    if let Some(order) = Order::of(block.iter().map(|u| u.key.as_str()))
+        && let Some(next) = block.iter().find(|u| order.sort_key(&u.key) > order.sort_key(&key))
+    {
+        return (line_start(source, next.range.start), format!("{line}\n"));
+    }
+    let (at, separator) = line_end(source, block[block.len() - 1].range.end);
+    (at, format!("{separator}{line}\n"))
+}
+
+/// The `use` items among `items`, in order.
+fn use_items<'a>(items: &'a [syn::Item], crate_name: Option<&str>) -> Vec<UseItem<'a>> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            syn::Item::Use(item) => Some(item),
+            _ => None,
+        })
+        .map(|item| {
+            let key: String = item.tree.to_token_stream().to_string().split_whitespace().collect();
+            let first = key.split("::").next().unwrap_or_default().trim_start_matches('{').to_string();
+            UseItem {
+                item,
+                range: item.span().byte_range(),
+                key,
+                origin: origin(&first, crate_name),
+            }
+        })
+        .collect()
+}
+
+/// Runs of `use` items with no other item and no blank line between them.
+fn blocks<'u, 'a>(source: &str, items: &[syn::Item], uses: &'u [UseItem<'a>]) -> Vec<&'u [UseItem<'a>]> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    for i in 1..=uses.len() {
+        let split = i == uses.len() || {
+            let (prev, next) = (&uses[i - 1], &uses[i]);
+            let between = source.get(prev.range.end..next.range.start).unwrap_or_default();
+            let adjacent = items
+                .iter()
+                .position(|item| matches!(item, syn::Item::Use(u) if std::ptr::eq(u, prev.item)))
+                .and_then(|k| items.get(k + 1))
+                .is_some_and(|item| matches!(item, syn::Item::Use(u) if std::ptr::eq(u, next.item)));
+            !adjacent || between.lines().skip(1).any(|line| line.trim().is_empty())
+        };
+        if split {
+            blocks.push(&uses[start..i]);
+            start = i;
+        }
+    }
+    blocks
+}
+
+/// The `{...}` group a use tree imports from `segments`, if it's exactly
+/// `segments::{...}`.
+fn group_at<'t>(tree: &'t syn::UseTree, segments: &[&str]) -> Option<&'t syn::UseGroup> {
+    match (tree, segments.split_first()) {
+        (syn::UseTree::Path(path), Some((first, rest))) if path.ident == first => group_at(&path.tree, rest),
+        (syn::UseTree::Group(group), None) => Some(group),
+        _ => None,
+    }
+}
+
+/// Add `name` to a `{...}` group, keeping one-per-line layout if it has it.
+fn merge_into_group(source: &str, group: &syn::UseGroup, name: &str) -> (usize, String) {
+    let items: Vec<(String, Range<usize>)> = group
+        .items
+        .iter()
+        .map(|tree| (tree.to_token_stream().to_string().split_whitespace().collect(), tree.span().byte_range()))
+        .collect();
+    let open = group.brace_token.span.open().byte_range().end;
+    let Some(first) = items.first() else {
+        return (open, name.to_string());
+    };
+
+    let position = match Order::of(items.iter().map(|(key, _)| key.as_str())) {
+        Some(order) => items
+            .iter()
+            .position(|(key, _)| order.sort_key(key) > order.sort_key(name))
+            .unwrap_or(items.len()),
+        None => items.len(),
+    };
+
+    let multi_line = source.get(open..first.1.start).unwrap_or_default().contains('\n');
+    if !multi_line {
+        return match items.get(position) {
+            Some((_, next)) => (next.start, format!("{name}, ")),
+            None => (items[items.len() - 1].1.end, format!(", {name}")),
+        };
+    }
+
+    let indent = indentation(source, first.1.start);
+    match items.get(position) {
+        Some((_, next)) if source[line_start(source, next.start)..next.start].trim().is_empty() => {
+            (line_start(source, next.start), format!("{indent}{name},\n"))
+        }
+        Some((_, next)) => (next.start, format!("{name}, ")),
+        None => {
+            let last = &items[items.len() - 1].1;
+            match group.items.pairs().next_back().and_then(|pair| pair.punct().map(|comma| comma.span.byte_range())) {
+                Some(comma) => (comma.end, format!("\n{indent}{name},")),
+                None => (last.end, format!(",\n{indent}{name}")),
+            }
+        }
+    }
+}
+
+/// With no imports yet: above the first item that isn't a `mod` or
+/// `extern crate` declaration, together with the comments attached to it.
+fn above_first_item(source: &str, scope: &Scope, line: &str) -> (usize, String) {
+    // Inner attributes and `//!` docs stay first
+    let floor = scope
+        .attrs
+        .iter()
+        .filter(|attr| matches!(attr.style, syn::AttrStyle::Inner(_)))
+        .map(|attr| attr.span().byte_range().end)
+        .fold(scope.start, usize::max);
+    let first = scope.items.iter().find(|item| match item {
+        syn::Item::Mod(m) => m.content.is_some(),
+        syn::Item::ExternCrate(_) => false,
+        _ => true,
+    });
+
+    let Some(first) = first else {
+        // Only declarations, or nothing: after the last of them
+        let end = scope.items.iter().map(|item| item.span().byte_range().end).max().unwrap_or(floor);
+        if end == 0 {
+            return (0, format!("{line}\n"));
+        }
+        let (at, separator) = line_end(source, end);
+        return (at, format!("{separator}\n{line}\n"));
+    };
+
+    let at = above_comments(source, line_start(source, first.span().byte_range().start), floor);
+    if at < floor {
+        // A module body on the line of its `{`, as in `mod m { fn f() {} }`
+        if scope.start > 0 {
+            return (first.span().byte_range().start, format!("{} ", line.trim_start()));
+        }
+        let (end, separator) = line_end(source, floor);
+        return (end, format!("{separator}\n{line}\n"));
+    }
+    // At file scope the imports are set apart from the items below them
+    let gap = if scope.start == 0 { "\n" } else { "" };
+    (at, format!("{line}\n{gap}"))
+}
+
+/// The start of the `//` comment lines directly above line start `at`, not
+/// going above `floor`.
+fn above_comments(source: &str, mut at: usize, floor: usize) -> usize {
+    while at > floor {
+        let above = line_start(source, at - 1);
+        let text = source[above..at].trim();
+        if !text.starts_with("//") || text.starts_with("//!") || above < floor {
+            break;
+        }
+        at = above;
+    }
+    at
+}
+
+/// The import an edit of [`import_edit`] adds, as a key that sorts it among
+/// others inserted in the same place: the path of a new `use` line, or the
+/// name added to a group. None for any other text.
+pub fn inserted_import(replacement: &str) -> Option<Vec<(u8, String)>> {
+    let text = replacement.trim().trim_matches(',').trim();
+    let path = text.strip_prefix("use ").and_then(|line| line.strip_suffix(';')).unwrap_or(text);
+    let is_path = path
+        .split("::")
+        .all(|segment| !segment.is_empty() && segment.chars().all(|c| c.is_alphanumeric() || c == '_'));
+    is_path.then(|| Order::Ascii.sort_key(path))
+}
+
+/// The start of the line containing `offset`.
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// Where a new line goes after the line containing `offset`, and the line
+/// break to put first if that line is the last and doesn't end with one.
+fn line_end(source: &str, offset: usize) -> (usize, &'static str) {
+    match source[offset..].find('\n') {
+        Some(i) => (offset + i + 1, ""),
+        None => (source.len(), "\n"),
+    }
+}
+
+/// The whitespace before the text on the line containing `offset`.
+fn indentation(source: &str, offset: usize) -> &str {
+    let start = line_start(source, offset);
+    let line = &source[start..];
+    &line[..line.len() - line.trim_start().len()]
+}
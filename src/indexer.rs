@@ -6,6 +6,7 @@ use crate::types::*;
 pub fn index_file(ast: &syn::File, module_path: &ModulePath, file_path: &Path) -> ModuleInfo {
     let mut info = ModuleInfo {
         file_path: file_path.to_path_buf(),
+        allowed_lints: crate::suppress::allowed_lints(&ast.attrs),
         ..Default::default()
     };
 
@@ -32,6 +33,11 @@ fn index_item(item: &syn::Item, module_path: &ModulePath, info: &mut ModuleInfo)
                     .collect(),
                 _ => Vec::new(),
             };
+            // A tuple struct's name is also its constructor function
+            let param_count = match &s.fields {
+                syn::Fields::Unnamed(unnamed) => Some(unnamed.unnamed.len()),
+                _ => None,
+            };
 
             info.items.push(ItemInfo {
                 name: s.ident.to_string(),
@@ -40,7 +46,33 @@ fn index_item(item: &syn::Item, module_path: &ModulePath, info: &mut ModuleInfo)
                 module: module_path.clone(),
                 fields,
                 variants: Vec::new(),
+                param_count,
+                span: Span::from_syn(&info.file_path, s.ident.span()),
+            });
+        }
+
+        syn::Item::Union(u) => {
+            let fields = u
+                .fields
+                .named
+                .iter()
+                .filter_map(|f| {
+                    f.ident.as_ref().map(|name| FieldInfo {
+                        name: name.to_string(),
+                        vis: Vis::from_syn(&f.vis),
+                    })
+                })
+                .collect();
+
+            info.items.push(ItemInfo {
+                name: u.ident.to_string(),
+                kind: ItemKind::Union,
+                vis: Vis::from_syn(&u.vis),
+                module: module_path.clone(),
+                fields,
+                variants: Vec::new(),
                 param_count: None,
+                span: Span::from_syn(&info.file_path, u.ident.span()),
             });
         }
 
@@ -86,6 +118,7 @@ fn index_item(item: &syn::Item, module_path: &ModulePath, info: &mut ModuleInfo)
                 fields: Vec::new(),
                 variants,
                 param_count: None,
+                span: Span::from_syn(&info.file_path, e.ident.span()),
             });
         }
 
@@ -98,6 +131,7 @@ fn index_item(item: &syn::Item, module_path: &ModulePath, info: &mut ModuleInfo)
                 fields: Vec::new(),
                 variants: Vec::new(),
                 param_count: None,
+                span: Span::from_syn(&info.file_path, t.ident.span()),
             });
         }
 
@@ -111,6 +145,7 @@ fn index_item(item: &syn::Item, module_path: &ModulePath, info: &mut ModuleInfo)
                 fields: Vec::new(),
                 variants: Vec::new(),
                 param_count: Some(param_count),
+                span: Span::from_syn(&info.file_path, f.sig.ident.span()),
             });
         }
 
@@ -123,6 +158,7 @@ fn index_item(item: &syn::Item, module_path: &ModulePath, info: &mut ModuleInfo)
                 fields: Vec::new(),
                 variants: Vec::new(),
                 param_count: None,
+                span: Span::from_syn(&info.file_path, t.ident.span()),
             });
         }
 
@@ -135,6 +171,7 @@ fn index_item(item: &syn::Item, module_path: &ModulePath, info: &mut ModuleInfo)
                 fields: Vec::new(),
                 variants: Vec::new(),
                 param_count: None,
+                span: Span::from_syn(&info.file_path, c.ident.span()),
             });
         }
 
@@ -147,12 +184,16 @@ fn index_item(item: &syn::Item, module_path: &ModulePath, info: &mut ModuleInfo)
                 fields: Vec::new(),
                 variants: Vec::new(),
                 param_count: None,
+                span: Span::from_syn(&info.file_path, s.ident.span()),
             });
         }
 
         syn::Item::Mod(m) => {
             let mod_name = m.ident.to_string();
             info.child_modules.push(mod_name.clone());
+            if m.attrs.iter().any(|a| a.path().is_ident("macro_use")) {
+                info.macro_use_modules.push(mod_name.clone());
+            }
 
             // If the module has inline content, index it
             if let Some((_, items)) = &m.content {
@@ -165,6 +206,7 @@ fn index_item(item: &syn::Item, module_path: &ModulePath, info: &mut ModuleInfo)
                     fields: Vec::new(),
                     variants: Vec::new(),
                     param_count: None,
+                    span: Span::from_syn(&info.file_path, m.ident.span()),
                 });
 
                 // Recursively index inline module items
@@ -182,6 +224,10 @@ fn index_item(item: &syn::Item, module_path: &ModulePath, info: &mut ModuleInfo)
                 info.impls.extend(child_info.impls);
             } else {
                 // External module declaration
+                let lints = crate::suppress::allowed_lints(&m.attrs);
+                if !lints.is_empty() {
+                    info.child_allowed_lints.push((mod_name.clone(), lints));
+                }
                 info.items.push(ItemInfo {
                     name: mod_name,
                     kind: ItemKind::Module,
@@ -190,6 +236,7 @@ fn index_item(item: &syn::Item, module_path: &ModulePath, info: &mut ModuleInfo)
                     fields: Vec::new(),
                     variants: Vec::new(),
                     param_count: None,
+                    span: Span::from_syn(&info.file_path, m.ident.span()),
                 });
             }
         }
@@ -242,11 +289,13 @@ fn index_item(item: &syn::Item, module_path: &ModulePath, info: &mut ModuleInfo)
                 info.items.push(ItemInfo {
                     name: ident.to_string(),
                     kind: ItemKind::Macro,
-                    vis: Vis::Private, // macro_rules! visibility is complex
+                    // macro_rules! are textually scoped; only exported ones have a path (at the crate root)
+                    vis: if m.attrs.iter().any(|a| a.path().is_ident("macro_export")) { Vis::Public } else { Vis::Private },
                     module: module_path.clone(),
                     fields: Vec::new(),
                     variants: Vec::new(),
                     param_count: None,
+                    span: Span::from_syn(&info.file_path, ident.span()),
                 });
             }
         }
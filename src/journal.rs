@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::baseline;
+use crate::fixer::FileChange;
+
+/// Where fix runs are recorded, relative to the crate root.
+pub const JOURNAL_DIR: &str = "target/rustpeek/fix-journal";
+
+const JOURNAL_VERSION: u32 = 1;
+
+/// How many runs are kept; older entries are deleted when a new one is recorded.
+const KEEP_ENTRIES: usize = 20;
+
+/// One `fix` run: the original content of every file it rewrote.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub version: u32,
+    /// Seconds since the Unix epoch
+    pub created: u64,
+    pub files: Vec<JournalFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JournalFile {
+    /// Path relative to the crate root, with `/` separators
+    pub path: String,
+    /// Content before the fix, byte for byte
+    pub original: String,
+    /// Hash of the content the fix wrote, to tell whether the file changed since
+    pub fixed_hash: String,
+}
+
+/// Files restored by [`undo`].
+pub struct UndoResult {
+    /// The journal entry that was reverted
+    pub entry: PathBuf,
+    pub files: Vec<String>,
+}
+
+impl JournalEntry {
+    pub fn new(crate_root: &Path, changes: &[FileChange]) -> Self {
+        JournalEntry {
+            version: JOURNAL_VERSION,
+            created: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            files: changes
+                .iter()
+                .map(|change| JournalFile {
+                    path: baseline::relative_path(crate_root, &change.path),
+                    original: change.before.clone(),
+                    fixed_hash: content_hash(&change.after),
+                })
+                .collect(),
+        }
+    }
+
+    /// Write the entry as the newest in the crate's journal and return its path.
+    pub fn record(&self, crate_root: &Path) -> Result<PathBuf, String> {
+        let dir = crate_root.join(JOURNAL_DIR);
+        std::fs::create_dir_all(&dir).map_err(|e| format!("could not create {}: {e}", dir.display()))?;
+
+        // Names sort in the order runs happened, also within one millisecond
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis());
+        let mut n = 0;
+        let mut path = dir.join(format!("{millis:016}-{n:04}.json"));
+        while path.exists() {
+            n += 1;
+            path = dir.join(format!("{millis:016}-{n:04}.json"));
+        }
+        self.save(&path)?;
+
+        for old in entries(crate_root).iter().rev().skip(KEEP_ENTRIES) {
+            let _ = std::fs::remove_file(old);
+        }
+        Ok(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, content).map_err(|e| format!("could not write {}: {e}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("could not read {}: {e}", path.display()))?;
+        let entry: JournalEntry =
+            serde_json::from_str(&content).map_err(|e| format!("invalid fix journal {}: {e}", path.display()))?;
+        if entry.version != JOURNAL_VERSION {
+            return Err(format!(
+                "fix journal {} has version {}, expected {JOURNAL_VERSION}",
+                path.display(),
+                entry.version
+            ));
+        }
+        Ok(entry)
+    }
+}
+
+/// Journal entries of a crate, oldest first.
+pub fn entries(crate_root: &Path) -> Vec<PathBuf> {
+    let Ok(dir) = std::fs::read_dir(crate_root.join(JOURNAL_DIR)) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<PathBuf> = dir
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    entries.sort();
+    entries
+}
+
+/// Revert the most recent `fix` run and remove it from the journal, so the
+/// next undo reverts the run before it.
+///
+/// Nothing is reverted if any file the run wrote has changed since, as that
+/// would throw away the later edits.
+pub fn undo(crate_root: &Path) -> Result<UndoResult, String> {
+    let Some(path) = entries(crate_root).pop() else {
+        return Err(format!("no fix run to undo (the journal in {JOURNAL_DIR} is empty)"));
+    };
+    let entry = JournalEntry::load(&path)?;
+
+    let changed: Vec<&str> = entry
+        .files
+        .iter()
+        .filter(|file| {
+            std::fs::read_to_string(crate_root.join(&file.path)).map_or(true, |content| content_hash(&content) != file.fixed_hash)
+        })
+        .map(|file| file.path.as_str())
+        .collect();
+    if !changed.is_empty() {
+        return Err(format!(
+            "not undoing the last fix run: {} changed since it was fixed (delete {} to discard the run instead)",
+            changed.iter().map(|p| format!("`{p}`")).collect::<Vec<_>>().join(", "),
+            path.display()
+        ));
+    }
+
+    for file in &entry.files {
+        let target = crate_root.join(&file.path);
+        crate::fixer::write_atomic(&target, &file.original)
+            .map_err(|e| format!("could not write {}: {e}", target.display()))?;
+    }
+    std::fs::remove_file(&path).map_err(|e| format!("could not remove {}: {e}", path.display()))?;
+
+    Ok(UndoResult {
+        entry: path,
+        files: entry.files.into_iter().map(|file| file.path).collect(),
+    })
+}
+
+fn content_hash(content: &str) -> String {
+    format!("{:016x}", baseline::fnv1a(content.as_bytes()))
+}
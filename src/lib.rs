@@ -1,43 +1,138 @@
 pub mod types;
+pub mod config;
+pub mod cfg;
 pub mod discovery;
 pub mod parser;
 pub mod indexer;
 pub mod validator;
+pub mod imports;
+pub mod similar;
 pub mod fixer;
+pub mod diff;
+pub mod journal;
+pub mod render;
+pub mod sarif;
+pub mod rustc_json;
+pub mod ci;
+pub mod lsp;
+pub mod mcp;
+pub mod rules;
+pub mod suppress;
+pub mod baseline;
+pub mod session;
+pub mod git;
+pub mod watch;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use types::*;
 
 /// Read the crate name from Cargo.toml (with `-` replaced by `_` for Rust identifiers).
-fn read_crate_name(crate_root: &Path) -> Option<String> {
+pub fn read_crate_name(crate_root: &Path) -> Option<String> {
     let cargo_toml = crate_root.join("Cargo.toml");
     let content = std::fs::read_to_string(cargo_toml).ok()?;
     for line in content.lines() {
         let trimmed = line.trim();
-        if trimmed.starts_with("name") {
-            if let Some(val) = trimmed.split('=').nth(1) {
-                let name = val.trim().trim_matches('"').trim_matches('\'');
-                return Some(name.replace('-', "_"));
-            }
+        if trimmed.starts_with("name")
+            && let Some(val) = trimmed.split('=').nth(1)
+        {
+            let name = val.trim().trim_matches('"').trim_matches('\'');
+            return Some(name.replace('-', "_"));
         }
     }
     None
 }
 
+/// Read a source file, preferring an in-memory copy from `overlay` if present.
+fn read_source(path: &Path, overlay: &HashMap<PathBuf, String>) -> std::io::Result<String> {
+    match overlay.get(path) {
+        Some(content) => Ok(strip_bom(content).to_string()),
+        None => read_file(path),
+    }
+}
+
+/// Read a source file as rustpeek sees it: without a leading byte order mark.
+///
+/// syn (like rustc) skips the BOM, so span byte offsets count from after it.
+/// Everything that maps spans back onto file text reads files through this.
+pub(crate) fn read_file(path: &Path) -> std::io::Result<String> {
+    let mut content = std::fs::read_to_string(path)?;
+    if content.starts_with(BOM) {
+        content.drain(..BOM.len());
+    }
+    Ok(content)
+}
+
+pub(crate) const BOM: &str = "\u{feff}";
+
+pub(crate) fn strip_bom(content: &str) -> &str {
+    content.strip_prefix(BOM).unwrap_or(content)
+}
+
 /// Run the full rustpeek analysis on a crate.
 ///
 /// - `crate_root`: path to the crate root directory (containing Cargo.toml)
 /// - `changed_files`: optional list of changed files to focus validation on.
-///   If None, all files are validated.
+///   If None, all files are validated. Files may be given relative to the
+///   working directory or crate root, or absolute; directories and glob
+///   patterns are expanded (see [`discovery::match_changed_files`]).
 pub fn analyze(crate_root: &Path, changed_files: Option<&[PathBuf]>) -> AnalysisResult {
+    analyze_with_overlay(crate_root, changed_files, &HashMap::new())
+}
+
+/// Same as [`analyze`], but files present in `overlay` are read from memory
+/// instead of disk. Used by the LSP server to check unsaved editor buffers.
+pub fn analyze_with_overlay(
+    crate_root: &Path,
+    changed_files: Option<&[PathBuf]>,
+    overlay: &HashMap<PathBuf, String>,
+) -> AnalysisResult {
+    match config::load(crate_root) {
+        Ok(config) => analyze_with_config(crate_root, changed_files, overlay, &config),
+        Err(message) => config_error(crate_root, message),
+    }
+}
+
+/// Same as [`analyze_with_overlay`], with an explicit configuration instead of
+/// the one loaded from the crate (e.g. after applying CLI overrides).
+pub fn analyze_with_config(
+    crate_root: &Path,
+    changed_files: Option<&[PathBuf]>,
+    overlay: &HashMap<PathBuf, String>,
+    config: &config::Config,
+) -> AnalysisResult {
     let crate_files = discovery::discover_crate(crate_root);
+    analyze_discovered(crate_root, &crate_files, changed_files, overlay, config, None)
+}
+
+/// The result of an analysis that couldn't start because the config is invalid.
+pub(crate) fn config_error(crate_root: &Path, message: String) -> AnalysisResult {
+    let config_file = crate_root.join(config::CONFIG_FILE);
+    let file = if config_file.exists() { config_file } else { crate_root.join("Cargo.toml") };
+    AnalysisResult {
+        diagnostics: vec![Diagnostic::error("invalid-config", Span::whole_file(&file), message)],
+    }
+}
+
+/// Analyze a crate whose files have already been discovered.
+///
+/// If `symbols` is None, the symbol table is built from `crate_files` once
+/// syntax validation passes; otherwise the given (cached) table is used.
+pub(crate) fn analyze_discovered(
+    crate_root: &Path,
+    crate_files: &discovery::CrateFiles,
+    changed_files: Option<&[PathBuf]>,
+    overlay: &HashMap<PathBuf, String>,
+    config: &config::Config,
+    symbols: Option<&SymbolTable>,
+) -> AnalysisResult {
     let src_dir = crate_root.join("src");
 
     // Determine which files to check
     let files_to_check: Vec<(&ModulePath, &PathBuf)> = if let Some(changed) = changed_files {
-        let changed_set: HashSet<_> = changed.iter().collect();
+        let matched = discovery::match_changed_files(crate_files, changed);
+        let changed_set: HashSet<_> = matched.files.iter().collect();
         crate_files
             .files
             .iter()
@@ -46,27 +141,26 @@ pub fn analyze(crate_root: &Path, changed_files: Option<&[PathBuf]>) -> Analysis
     } else {
         crate_files.files.iter().collect()
     };
+    let files_to_check: Vec<_> = files_to_check
+        .into_iter()
+        .filter(|(_, path)| !config.is_excluded(crate_root, path))
+        .collect();
 
     // ── Pass 1: Syntax validation ──
     let mut all_diagnostics = Vec::new();
     let mut has_syntax_errors = false;
 
-    let mut parsed_files: Vec<(&ModulePath, syn::File, &PathBuf)> = Vec::new();
+    let mut parsed_files: Vec<(&ModulePath, syn::File, &PathBuf, String)> = Vec::new();
 
     for (module_path, file_path) in &files_to_check {
-        let source = match std::fs::read_to_string(file_path) {
+        let source = match read_source(file_path, overlay) {
             Ok(s) => s,
             Err(e) => {
-                all_diagnostics.push(Diagnostic {
-                    severity: Severity::Error,
-                    file: file_path.to_path_buf(),
-                    line: 0,
-                    column: 0,
-                    message: format!("could not read file: {e}"),
-                    error_code: None,
-                    hint: None,
-                    fix: None,
-                });
+                all_diagnostics.push(Diagnostic::error(
+                    "unreadable-file",
+                    Span::whole_file(file_path),
+                    format!("could not read file: {e}"),
+                ));
                 continue;
             }
         };
@@ -78,48 +172,90 @@ pub fn analyze(crate_root: &Path, changed_files: Option<&[PathBuf]>) -> Analysis
             continue;
         }
 
-        if let Some(ast) = parser::parse_file(&source) {
-            parsed_files.push((module_path, ast, file_path));
+        if let Some(mut ast) = parser::parse_file(&source) {
+            cfg::strip_disabled(&mut ast, config);
+            parsed_files.push((module_path, ast, file_path, source));
         }
     }
 
     // If there are syntax errors, stop here — don't run Pass 2
     if has_syntax_errors {
-        return AnalysisResult {
+        let mut result = AnalysisResult {
             diagnostics: all_diagnostics,
         };
+        config.apply(&mut result);
+        return result;
     }
 
     // ── Pass 2: Crate indexing + validation ──
 
     let crate_name = read_crate_name(crate_root);
 
-    // Step 1: Build the symbol table from ALL files in the crate
-    let mut symbol_table = SymbolTable::new();
-
-    for (module_path, file_path) in &crate_files.files {
-        let source = match std::fs::read_to_string(file_path) {
-            Ok(s) => s,
-            Err(_) => continue,
-        };
-
-        let ast = match parser::parse_file(&source) {
-            Some(ast) => ast,
-            None => continue,
-        };
-
-        let module_info = indexer::index_file(&ast, module_path, file_path);
-        symbol_table.modules.insert(module_path.clone(), module_info);
-    }
+    // Step 1: Build the symbol table from ALL files in the crate (unless cached)
+    let built;
+    let symbol_table = match symbols {
+        Some(symbols) => symbols,
+        None => {
+            built = build_symbol_table(crate_files, overlay, config);
+            &built
+        }
+    };
 
     // Step 2: Validate only the changed files against the full symbol table
-    for (module_path, ast, file_path) in &parsed_files {
-        let diagnostics =
-            validator::validate_file(ast, file_path, module_path, &symbol_table, &src_dir, crate_name.as_deref());
+    for (module_path, ast, file_path, source) in &parsed_files {
+        let diagnostics = validator::validate_file(
+            ast,
+            file_path,
+            source,
+            module_path,
+            symbol_table,
+            &src_dir,
+            crate_name.as_deref(),
+        );
         all_diagnostics.extend(diagnostics);
     }
 
-    AnalysisResult {
+    // Step 3: Drop diagnostics silenced by `// rustpeek: allow(...)` comments,
+    // then those under a rustc `#[allow(...)]` for a lint their rule mirrors
+    for (module_path, ast, file_path, source) in &parsed_files {
+        suppress::apply(&mut all_diagnostics, file_path, source, config);
+        suppress::apply_allows(&mut all_diagnostics, file_path, ast, module_path, symbol_table);
+    }
+
+    let mut result = AnalysisResult {
         diagnostics: all_diagnostics,
+    };
+    config.apply(&mut result);
+    result
+}
+
+/// Index every file in the crate into a symbol table.
+/// Files that can't be read or parsed are skipped.
+pub fn build_symbol_table(
+    crate_files: &discovery::CrateFiles,
+    overlay: &HashMap<PathBuf, String>,
+    config: &config::Config,
+) -> SymbolTable {
+    let mut symbol_table = SymbolTable::new();
+
+    for (module_path, file_path) in &crate_files.files {
+        if let Some(module_info) = index_module(module_path, file_path, overlay, config) {
+            symbol_table.modules.insert(module_path.clone(), module_info);
+        }
     }
+
+    symbol_table
+}
+
+/// Read, parse and index a single module file. Returns None if it can't be read or parsed.
+pub(crate) fn index_module(
+    module_path: &ModulePath,
+    file_path: &Path,
+    overlay: &HashMap<PathBuf, String>,
+    config: &config::Config,
+) -> Option<ModuleInfo> {
+    let source = read_source(file_path, overlay).ok()?;
+    let mut ast = parser::parse_file(&source)?;
+    cfg::strip_disabled(&mut ast, config);
+    Some(indexer::index_file(&ast, module_path, file_path))
 }
@@ -0,0 +1,617 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use serde_json::{Value, json};
+
+use crate::types::*;
+use crate::{config, discovery, validator};
+
+/// Run the LSP server on stdin/stdout until the client sends `exit`.
+pub fn run() -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    let stdout = io::stdout();
+    let mut writer = stdout.lock();
+
+    let mut server = Server::default();
+    while let Some(message) = read_message(&mut reader)? {
+        let message = match message {
+            Ok(message) => message,
+            Err(e) => {
+                // The framing is intact, so the next message can still be read
+                send(&mut writer, &json!({
+                    "jsonrpc": "2.0",
+                    "id": Value::Null,
+                    "error": { "code": -32700, "message": format!("parse error: {e}") },
+                }))?;
+                continue;
+            }
+        };
+        let keep_running = server.handle(&message, &mut writer)?;
+        // Everything kept between messages holds rustpeek's own spans, so the
+        // locations proc_macro2 recorded while parsing can go
+        proc_macro2::extra::invalidate_current_thread_spans();
+        if !keep_running {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Server state: open documents and their latest diagnostics.
+#[derive(Default)]
+struct Server {
+    /// Contents of open documents, keyed by file path. Used as the analysis overlay.
+    documents: HashMap<PathBuf, String>,
+    /// Diagnostics from the last analysis of each open document, used for code actions.
+    diagnostics: HashMap<PathBuf, Vec<Diagnostic>>,
+    /// Symbol table of each crate as of its last analysis, keyed by crate root.
+    symbol_tables: HashMap<PathBuf, Rc<SymbolTable>>,
+    shutdown_requested: bool,
+}
+
+impl Server {
+    /// Handle one incoming message. Returns false when the server should exit.
+    fn handle(&mut self, message: &Value, out: &mut impl Write) -> io::Result<bool> {
+        let method = message["method"].as_str().unwrap_or("");
+        let id = message.get("id").cloned();
+        let params = &message["params"];
+
+        match method {
+            "initialize" => {
+                let result = json!({
+                    "capabilities": {
+                        "textDocumentSync": {
+                            "openClose": true,
+                            "change": 1, // full document sync
+                            "save": { "includeText": true },
+                        },
+                        "codeActionProvider": { "codeActionKinds": ["quickfix"] },
+                        "definitionProvider": true,
+                        "documentSymbolProvider": true,
+                    },
+                    "serverInfo": {
+                        "name": "rustpeek",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                });
+                send_response(out, id, result)?;
+            }
+            "shutdown" => {
+                self.shutdown_requested = true;
+                send_response(out, id, Value::Null)?;
+            }
+            "exit" => return Ok(false),
+            "textDocument/didOpen" => {
+                let doc = &params["textDocument"];
+                if let (Some(path), Some(text)) = (uri_param(doc), doc["text"].as_str()) {
+                    self.documents.insert(path.clone(), text.to_string());
+                    self.check(&path, out)?;
+                }
+            }
+            "textDocument/didChange" => {
+                let path = uri_param(&params["textDocument"]);
+                // Full sync: the last change carries the whole document
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                if let (Some(path), Some(text)) = (path, text) {
+                    self.documents.insert(path.clone(), text.to_string());
+                    self.check(&path, out)?;
+                }
+            }
+            "textDocument/didSave" => {
+                if let Some(path) = uri_param(&params["textDocument"]) {
+                    if let Some(text) = params["text"].as_str() {
+                        self.documents.insert(path.clone(), text.to_string());
+                    }
+                    self.check(&path, out)?;
+                }
+            }
+            "textDocument/didClose" => {
+                if let Some(path) = uri_param(&params["textDocument"]) {
+                    self.documents.remove(&path);
+                    self.diagnostics.remove(&path);
+                    publish_diagnostics(out, &path, Vec::new())?;
+                    // Other open files may have depended on the unsaved buffer
+                    self.check(&path, out)?;
+                }
+            }
+            "textDocument/codeAction" => {
+                let result = uri_param(&params["textDocument"])
+                    .map(|path| self.code_actions(&path, &params["range"]))
+                    .unwrap_or_default();
+                send_response(out, id, Value::Array(result))?;
+            }
+            "textDocument/definition" => {
+                let result = uri_param(&params["textDocument"])
+                    .and_then(|path| self.definition(&path, &params["position"]))
+                    .unwrap_or(Value::Null);
+                send_response(out, id, result)?;
+            }
+            "textDocument/documentSymbol" => {
+                let result = uri_param(&params["textDocument"])
+                    .map(|path| self.document_symbols(&path))
+                    .unwrap_or_default();
+                send_response(out, id, Value::Array(result))?;
+            }
+            _ => {
+                // Unknown requests get an error; unknown notifications are ignored
+                if let Some(id) = id {
+                    let code = if self.shutdown_requested { -32600 } else { -32601 };
+                    send(out, &json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": format!("unhandled method `{method}`") },
+                    }))?;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Re-analyze every open document in the crate containing `path` and publish the results.
+    fn check(&mut self, path: &Path, out: &mut impl Write) -> io::Result<()> {
+        let Some(crate_root) = find_crate_root(path) else {
+            return Ok(());
+        };
+
+        let open_in_crate: Vec<PathBuf> = self
+            .documents
+            .keys()
+            .filter(|p| p.starts_with(&crate_root))
+            .cloned()
+            .collect();
+        if open_in_crate.is_empty() {
+            self.symbol_tables.remove(&crate_root);
+            return Ok(());
+        }
+
+        let result = match config::load(&crate_root) {
+            Ok(config) => {
+                let crate_files = discovery::discover_crate(&crate_root);
+                let symbols = crate::build_symbol_table(&crate_files, &self.documents, &config);
+                let result = crate::analyze_discovered(
+                    &crate_root,
+                    &crate_files,
+                    Some(&open_in_crate),
+                    &self.documents,
+                    &config,
+                    Some(&symbols),
+                );
+                self.symbol_tables.insert(crate_root.clone(), Rc::new(symbols));
+                result
+            }
+            Err(message) => {
+                self.symbol_tables.remove(&crate_root);
+                crate::config_error(&crate_root, message)
+            }
+        };
+
+        let mut by_file: HashMap<PathBuf, Vec<Diagnostic>> =
+            open_in_crate.iter().map(|p| (p.clone(), Vec::new())).collect();
+        for diag in result.diagnostics {
+            by_file.entry(diag.file().to_path_buf()).or_default().push(diag);
+        }
+
+        for (file, diagnostics) in by_file {
+            let text = self.document_text(&file);
+            let lsp_diagnostics = diagnostics.iter().map(|d| to_lsp_diagnostic(d, &text)).collect();
+            publish_diagnostics(out, &file, lsp_diagnostics)?;
+            self.diagnostics.insert(file, diagnostics);
+        }
+
+        Ok(())
+    }
+
+    /// The crate's symbol table from its last analysis, or, if it has none,
+    /// one indexed now, reading open documents from their buffers.
+    fn symbol_table(&mut self, crate_root: &Path) -> Rc<SymbolTable> {
+        let documents = &self.documents;
+        let symbols = self.symbol_tables.entry(crate_root.to_path_buf()).or_insert_with(|| {
+            let crate_files = discovery::discover_crate(crate_root);
+            let config = config::load(crate_root).unwrap_or_default();
+            Rc::new(crate::build_symbol_table(&crate_files, documents, &config))
+        });
+        Rc::clone(symbols)
+    }
+
+    /// Current text of a file: the open buffer if there is one, otherwise the file on disk.
+    fn document_text(&self, path: &Path) -> String {
+        self.documents
+            .get(path)
+            .cloned()
+            .unwrap_or_else(|| crate::read_file(path).unwrap_or_default())
+    }
+
+    /// Turn the fixes of diagnostics overlapping `range` into quickfix code actions.
+    fn code_actions(&self, path: &Path, range: &Value) -> Vec<Value> {
+        let Some(diagnostics) = self.diagnostics.get(path) else {
+            return Vec::new();
+        };
+
+        let start_line = range["start"]["line"].as_u64().unwrap_or(0) as usize + 1;
+        let end_line = range["end"]["line"].as_u64().unwrap_or(u64::MAX) as usize + 1;
+        let text = self.document_text(path);
+
+        diagnostics
+            .iter()
+            .filter(|d| d.line() >= start_line && d.line() <= end_line)
+            .filter_map(|d| {
+                let fix = d.fix.as_ref()?;
+                // Edits are grouped by document, converted against its current (possibly unsaved) text
+                let mut changes = serde_json::Map::new();
+                for edit in &fix.edits {
+                    let edit_text = self.document_text(&edit.file);
+                    let edits = changes.entry(path_to_uri(&edit.file)).or_insert_with(|| json!([]));
+                    edits.as_array_mut()?.push(json!({
+                        "range": lsp_range(&edit_text, &edit.span(&edit_text)),
+                        "newText": edit.replacement,
+                    }));
+                }
+                if changes.is_empty() {
+                    return None;
+                }
+                Some(json!({
+                    "title": fix_title(fix),
+                    "kind": "quickfix",
+                    "isPreferred": fix.applicability == Applicability::MachineApplicable,
+                    "diagnostics": [to_lsp_diagnostic(d, &text)],
+                    "edit": { "changes": changes },
+                }))
+            })
+            .collect()
+    }
+
+    /// Resolve the identifier under the cursor to the location of its definition.
+    fn definition(&mut self, path: &Path, position: &Value) -> Option<Value> {
+        let text = self.document_text(path);
+        let (line, utf16_col) = lsp_position(position)?;
+        let line_text = text.lines().nth(line)?;
+        let word = word_at(line_text, char_col_from_utf16(line_text, utf16_col))?;
+
+        let crate_root = find_crate_root(path)?;
+        let symbols = self.symbol_table(&crate_root);
+        let crate_name = crate::read_crate_name(&crate_root);
+
+        let (module_path, module_info) = symbols.modules.iter().find(|(_, m)| m.file_path == path)?;
+
+        // 1. Items defined in this module
+        if let Some(item) = module_info.items.iter().find(|i| i.name == word) {
+            if item.kind == ItemKind::Module
+                && let Some(child) = symbols.modules.get(&module_path.child(&word))
+            {
+                return Some(self.location(&Span::whole_file(&child.file_path)));
+            }
+            return Some(self.location(&item.span));
+        }
+
+        // 2. Names brought in by `use`
+        for use_info in module_info.uses.iter().filter(|u| !u.is_glob && u.alias == word) {
+            let resolved =
+                validator::resolve_use_path(&use_info.path, module_path, &symbols, crate_name.as_deref());
+            if let Some(resolved) = resolved
+                && let Some(location) = self.locate_path(&symbols, &resolved)
+            {
+                return Some(location);
+            }
+        }
+
+        // 3. Globs, then a unique item anywhere in the crate
+        for use_info in module_info.uses.iter().filter(|u| u.is_glob) {
+            let resolved =
+                validator::resolve_use_path(&use_info.path, module_path, &symbols, crate_name.as_deref());
+            if let Some(mut resolved) = resolved {
+                resolved.push(word.clone());
+                if let Some(location) = self.locate_path(&symbols, &resolved) {
+                    return Some(location);
+                }
+            }
+        }
+
+        let matches = symbols.find_item(&word);
+        if let [item] = matches.as_slice() {
+            return Some(self.location(&item.span));
+        }
+
+        None
+    }
+
+    /// Locate an absolute crate path (`crate::a::Item` or `crate::a`) in the symbol table.
+    fn locate_path(&self, symbols: &SymbolTable, resolved: &[String]) -> Option<Value> {
+        if let Some(module) = symbols.modules.get(&ModulePath(resolved.to_vec())) {
+            return Some(self.location(&Span::whole_file(&module.file_path)));
+        }
+        if resolved.len() < 2 {
+            return None;
+        }
+        let name = &resolved[resolved.len() - 1];
+        let parent = ModulePath(resolved[..resolved.len() - 1].to_vec());
+        let module = symbols.modules.get(&parent)?;
+        let item = module.items.iter().find(|i| i.name == *name)?;
+        Some(self.location(&item.span))
+    }
+
+    /// Build an LSP `Location` for a span.
+    fn location(&self, span: &Span) -> Value {
+        let text = self.document_text(&span.file);
+        json!({ "uri": path_to_uri(&span.file), "range": lsp_range(&text, span) })
+    }
+
+    /// List the items defined in a file as LSP `DocumentSymbol`s.
+    fn document_symbols(&mut self, path: &Path) -> Vec<Value> {
+        let Some(crate_root) = find_crate_root(path) else {
+            return Vec::new();
+        };
+        let symbols = self.symbol_table(&crate_root);
+        let Some(module_info) = symbols.modules.values().find(|m| m.file_path == path) else {
+            return Vec::new();
+        };
+
+        let text = self.document_text(path);
+        module_info
+            .items
+            .iter()
+            .map(|item| {
+                let range = lsp_range(&text, &item.span);
+                json!({
+                    "name": item.name,
+                    "kind": symbol_kind(&item.kind),
+                    "range": range,
+                    "selectionRange": range,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Walk up from a file to the nearest directory containing a Cargo.toml.
+fn find_crate_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// LSP `SymbolKind` for an item kind.
+fn symbol_kind(kind: &ItemKind) -> u32 {
+    match kind {
+        ItemKind::Module => 2,
+        ItemKind::Enum => 10,
+        ItemKind::Trait => 11,
+        ItemKind::Function | ItemKind::Macro => 12,
+        ItemKind::Static => 13,
+        ItemKind::Const => 14,
+        ItemKind::Struct | ItemKind::Union => 23,
+        ItemKind::TypeAlias => 26,
+    }
+}
+
+// ── Diagnostics and fixes ──
+
+fn to_lsp_diagnostic(diag: &Diagnostic, text: &str) -> Value {
+    let mut range = lsp_range(text, &diag.span);
+    if diag.span.start == diag.span.end {
+        // No extent; highlight the word at the start instead of nothing
+        let line = diag.line().saturating_sub(1);
+        let line_text = text.lines().nth(line).unwrap_or("");
+        let start = diag.column().saturating_sub(1);
+        let end = word_end(line_text, start).max(start + 1);
+        range["end"] = position(line, utf16_col(line_text, end));
+    }
+
+    let mut message = diag.message.clone();
+    for note in &diag.notes {
+        message.push_str(&format!("\nnote: {note}"));
+    }
+    for help in &diag.help {
+        message.push_str(&format!("\nhelp: {help}"));
+    }
+
+    let related: Vec<Value> = diag
+        .labels
+        .iter()
+        .map(|label| {
+            let label_text = crate::read_file(&label.span.file).unwrap_or_default();
+            json!({
+                "location": { "uri": path_to_uri(&label.span.file), "range": lsp_range(&label_text, &label.span) },
+                "message": label.message,
+            })
+        })
+        .collect();
+
+    let mut value = json!({
+        "range": range,
+        "severity": match diag.severity {
+            Severity::Error => 1,
+            Severity::Suggestion => 3,
+        },
+        "source": "rustpeek",
+        "code": diag.error_code.as_deref().unwrap_or(&diag.rule),
+        "message": message,
+    });
+    if !related.is_empty() {
+        value["relatedInformation"] = json!(related);
+    }
+    value
+}
+
+/// LSP range of a span, with columns converted to UTF-16 against `text`.
+fn lsp_range(text: &str, span: &Span) -> Value {
+    let convert = |pos: &crate::types::Position| {
+        let line = pos.line.saturating_sub(1);
+        let line_text = text.lines().nth(line).unwrap_or("");
+        position(line, utf16_col(line_text, pos.column.saturating_sub(1)))
+    };
+    json!({ "start": convert(&span.start), "end": convert(&span.end) })
+}
+
+fn publish_diagnostics(out: &mut impl Write, path: &Path, diagnostics: Vec<Value>) -> io::Result<()> {
+    send(out, &json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": path_to_uri(path), "diagnostics": diagnostics },
+    }))
+}
+
+/// The fix message as a code action title, capitalized like editors' own actions.
+fn fix_title(fix: &Fix) -> String {
+    let mut chars = fix.message.chars();
+    chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+// ── Positions ──
+//
+// rustpeek (via proc_macro2) counts columns in characters; LSP counts UTF-16 code units.
+
+fn position(line: usize, character: usize) -> Value {
+    json!({ "line": line, "character": character })
+}
+
+fn lsp_position(value: &Value) -> Option<(usize, usize)> {
+    Some((value["line"].as_u64()? as usize, value["character"].as_u64()? as usize))
+}
+
+/// UTF-16 offset of the `char_col`-th character of a line.
+fn utf16_col(line_text: &str, char_col: usize) -> usize {
+    line_text.chars().take(char_col).map(char::len_utf16).sum()
+}
+
+/// Character index corresponding to a UTF-16 offset in a line.
+fn char_col_from_utf16(line_text: &str, utf16: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in line_text.chars().enumerate() {
+        if units >= utf16 {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    line_text.chars().count()
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Character index just past the identifier starting at `char_col`.
+fn word_end(line_text: &str, char_col: usize) -> usize {
+    char_col + line_text.chars().skip(char_col).take_while(|c| is_ident_char(*c)).count()
+}
+
+/// The identifier touching character index `char_col`, if any.
+fn word_at(line_text: &str, char_col: usize) -> Option<String> {
+    let chars: Vec<char> = line_text.chars().collect();
+    let mut start = char_col.min(chars.len());
+    while start > 0 && is_ident_char(chars[start - 1]) {
+        start -= 1;
+    }
+    let end = word_end(line_text, start);
+    if end == start {
+        return None;
+    }
+    Some(chars[start..end].iter().collect())
+}
+
+// ── URIs ──
+
+fn uri_param(text_document: &Value) -> Option<PathBuf> {
+    uri_to_path(text_document["uri"].as_str()?)
+}
+
+/// Convert a `file://` URI to a filesystem path.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let decoded = percent_decode(rest);
+    // `file:///C:/foo` → `C:/foo` on Windows
+    let bytes = decoded.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'/' && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        return Some(PathBuf::from(&decoded[1..]));
+    }
+    Some(PathBuf::from(decoded))
+}
+
+/// Convert a filesystem path to a `file://` URI.
+pub(crate) fn path_to_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    uri.push_str(&percent_encode_path(&path));
+    uri
+}
+
+/// Percent-encode a `/`-separated path for use in a URI.
+pub(crate) fn percent_encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// ── JSON-RPC framing ──
+
+/// Read one `Content-Length`-framed message. Returns None at end of input,
+/// and the parse error if the body isn't JSON.
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<serde_json::Result<Value>>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            content_length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let Some(length) = content_length else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header"));
+    };
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)))
+}
+
+fn send(out: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(out, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    out.flush()
+}
+
+fn send_response(out: &mut impl Write, id: Option<Value>, result: Value) -> io::Result<()> {
+    send(out, &json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
@@ -1,13 +1,153 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
 use std::process;
 
-use rustpeek::types::{AnalysisResult, Severity};
+use rustpeek::config::Config;
+use rustpeek::fixer::{FixFilter, FixPlan};
+use rustpeek::git::GitSelection;
+use rustpeek::render::{ColorChoice, Renderer};
+use rustpeek::types::{AnalysisResult, Applicability, Fix, Severity};
 
-fn main() {
-    let args: Vec<String> = std::env::args().collect();
+/// How `check` prints its result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Format {
+    /// rustc-style text
+    #[default]
+    Human,
+    Json,
+    /// SARIF 2.1.0 log
+    Sarif,
+    /// Cargo `--message-format=json` lines
+    RustcJson,
+    /// GitHub Actions workflow commands
+    Github,
+    /// GitLab Code Quality report
+    Gitlab,
+}
+
+impl Format {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "human" => Some(Format::Human),
+            "json" => Some(Format::Json),
+            "sarif" => Some(Format::Sarif),
+            "rustc-json" => Some(Format::RustcJson),
+            "github" => Some(Format::Github),
+            "gitlab" => Some(Format::Gitlab),
+            _ => None,
+        }
+    }
+}
+
+/// Parsed command-line flags and positional arguments.
+#[derive(Default)]
+struct Options {
+    format: Format,
+    color: ColorChoice,
+    watch: bool,
+    git: Option<GitSelection>,
+    /// Baseline file to read (`check`) or write (`baseline write`)
+    baseline: Option<PathBuf>,
+    /// `fix`: show what would change instead of writing files
+    dry_run: bool,
+    /// `fix`: revert the last fix run
+    undo: bool,
+    /// `fix`: which diagnostics to fix
+    fix_filter: FixFilter,
+    /// `fix`: ask before applying each fix
+    interactive: bool,
+    /// Config settings from flags, layered over the crate's config file
+    config: Config,
+    positional: Vec<String>,
+}
+
+/// Split a comma-separated flag value into its items.
+fn list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect()
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut iter = args.iter();
 
-    let json_mode = args.iter().any(|a| a == "--json");
-    let positional: Vec<&String> = args.iter().skip(1).filter(|a| !a.starts_with("--")).collect();
+    while let Some(arg) = iter.next() {
+        // Support both `--flag value` and `--flag=value`
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |name: &str| {
+            inline_value
+                .clone()
+                .or_else(|| iter.next().cloned())
+                .ok_or_else(|| format!("{name} requires a value"))
+        };
+
+        match flag {
+            "--json" => options.format = Format::Json,
+            "--format" => {
+                let format = value("--format")?;
+                options.format = Format::parse(&format)
+                    .ok_or_else(|| format!("unknown --format `{format}` (expected `human`, `json`, `sarif`, `rustc-json`, `github` or `gitlab`)"))?;
+            }
+            "--color" => {
+                let choice = value("--color")?;
+                options.color = ColorChoice::parse(&choice)
+                    .ok_or_else(|| format!("unknown --color `{choice}` (expected `auto`, `always` or `never`)"))?;
+            }
+            "--watch" => options.watch = true,
+            "--dry-run" | "--diff" => options.dry_run = true,
+            "--undo" => options.undo = true,
+            "--only" => options.fix_filter.only = Some(list(&value("--only")?)),
+            "--exclude" => options.fix_filter.exclude.extend(list(&value("--exclude")?)),
+            "--interactive" => options.interactive = true,
+            "--maybe-incorrect" => options.fix_filter.maybe_incorrect = true,
+            "--changed-since" => options.git = Some(GitSelection::ChangedSince(value("--changed-since")?)),
+            "--staged" => options.git = Some(GitSelection::Staged),
+            "--unstaged" => options.git = Some(GitSelection::Unstaged),
+            "--baseline" => options.baseline = Some(PathBuf::from(value("--baseline")?)),
+            "--enable" => options.config.enable = Some(list(&value("--enable")?)),
+            "--disable" => options.config.disable.extend(list(&value("--disable")?)),
+            "--severity" => {
+                let setting = value("--severity")?;
+                let (code, level) = setting
+                    .split_once('=')
+                    .ok_or_else(|| format!("--severity expects CODE=LEVEL, got `{setting}`"))?;
+                let severity = match level {
+                    "error" => Severity::Error,
+                    "suggestion" => Severity::Suggestion,
+                    _ => return Err(format!("unknown severity `{level}` (expected `error` or `suggestion`)")),
+                };
+                options.config.severity.insert(code.to_string(), severity);
+            }
+            "--exclude-path" => options.config.exclude.push(value("--exclude-path")?),
+            "--fixable" => options.config.fixable = Some(list(&value("--fixable")?)),
+            "--features" => options.config.features = Some(list(&value("--features")?)),
+            "--cfg" => options.config.cfg.get_or_insert_with(Vec::new).push(value("--cfg")?),
+            _ if flag.starts_with("--") => return Err(format!("unknown option `{flag}`")),
+            _ => options.positional.push(arg.clone()),
+        }
+    }
+
+    options.fix_filter.validate()?;
+    Ok(options)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {e}");
+            print_usage();
+            process::exit(2);
+        }
+    };
+    let format = options.format;
+    let json_mode = format == Format::Json;
+    let color = options.color.use_color();
+    let positional = &options.positional;
 
     if positional.is_empty() {
         print_usage();
@@ -15,9 +155,29 @@ fn main() {
     }
 
     let subcommand = positional[0].as_str();
+    if subcommand == "lsp" || subcommand == "mcp" {
+        let served = if subcommand == "lsp" {
+            rustpeek::lsp::run()
+        } else {
+            rustpeek::mcp::run()
+        };
+        if let Err(e) = served {
+            eprintln!("error: {subcommand} server failed: {e}");
+            process::exit(2);
+        }
+        process::exit(0);
+    }
+
+    let baseline_write = subcommand == "baseline";
+    if baseline_write && positional.get(1).map(String::as_str) != Some("write") {
+        eprintln!("error: expected `rustpeek baseline write <crate-path>`");
+        process::exit(2);
+    }
+
     let (fix_mode, path_start) = match subcommand {
         "check" => (false, 1),
         "fix" => (true, 1),
+        "baseline" => (false, 2),
         // No subcommand — treat first arg as crate path (backwards compat)
         _ => (false, 0),
     };
@@ -27,53 +187,228 @@ fn main() {
         process::exit(2);
     }
 
-    let crate_root = PathBuf::from(positional[path_start]);
+    let crate_root = PathBuf::from(&positional[path_start]);
 
     if !crate_root.join("Cargo.toml").exists() {
         eprintln!("error: no Cargo.toml found in {}", crate_root.display());
         process::exit(2);
     }
 
-    let changed_files: Option<Vec<PathBuf>> = if positional.len() > path_start + 1 {
+    let mut changed_files: Option<Vec<PathBuf>> = if positional.len() > path_start + 1 {
         Some(positional[path_start + 1..].iter().map(PathBuf::from).collect())
     } else {
         None
     };
 
-    let result = rustpeek::analyze(&crate_root, changed_files.as_deref());
+    // Warn about explicit paths that don't select anything, rather than silently checking nothing
+    if let Some(changed) = &changed_files {
+        let crate_files = rustpeek::discovery::discover_crate(&crate_root);
+        let matched = rustpeek::discovery::match_changed_files(&crate_files, changed);
+        for path in &matched.unmatched {
+            eprintln!("warning: `{}` does not match any source file of the crate", path.display());
+        }
+    }
 
-    if fix_mode {
-        run_fix(result, json_mode);
+    let filtered = options.fix_filter.only.is_some()
+        || !options.fix_filter.exclude.is_empty()
+        || options.fix_filter.maybe_incorrect;
+    if (options.dry_run || options.undo || options.interactive || filtered) && !fix_mode {
+        eprintln!(
+            "error: --dry-run, --undo, --only, --exclude, --maybe-incorrect and --interactive are only supported with `fix`"
+        );
+        process::exit(2);
+    }
+    if options.undo {
+        if options.dry_run
+            || options.interactive
+            || filtered
+            || options.watch
+            || options.git.is_some()
+            || changed_files.is_some()
+        {
+            eprintln!("error: `fix --undo` always reverts the whole last fix run");
+            process::exit(2);
+        }
+        run_fix_undo(&crate_root, json_mode);
+    }
+    if options.interactive && (options.dry_run || json_mode) {
+        eprintln!("error: --interactive can't be combined with --dry-run or --json");
+        process::exit(2);
+    }
+    if options.baseline.is_some() && (fix_mode || options.watch) {
+        eprintln!("error: --baseline is only supported with `check` and `baseline write`");
+        process::exit(2);
+    }
+    if !matches!(format, Format::Human | Format::Json) && (fix_mode || baseline_write || options.watch) {
+        eprintln!("error: this --format is only supported with `check`");
+        process::exit(2);
+    }
+    if baseline_write && (options.watch || options.git.is_some() || changed_files.is_some()) {
+        eprintln!("error: `baseline write` always records the whole crate");
+        process::exit(2);
+    }
+
+    let mut overlay = HashMap::new();
+    if let Some(selection) = &options.git {
+        let git_files = match rustpeek::git::changed_files(&crate_root, selection) {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("error: {e}");
+                process::exit(2);
+            }
+        };
+        // Check what's about to be committed, not the working tree. Fixes
+        // edit the working tree, so they must be computed against it.
+        if *selection == GitSelection::Staged && !options.watch && !fix_mode {
+            match rustpeek::git::staged_contents(&crate_root, &git_files) {
+                Ok(contents) => overlay = contents,
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    process::exit(2);
+                }
+            }
+        }
+        changed_files.get_or_insert_with(Vec::new).extend(git_files);
+
+        // Nothing changed in git means nothing to check
+        if changed_files.as_ref().is_some_and(|c| c.is_empty()) && !options.watch {
+            if format != Format::Human {
+                run_check(&crate_root, AnalysisResult { diagnostics: Vec::new() }, format, color);
+            }
+            println!("rustpeek: no changed files");
+            process::exit(0);
+        }
+    }
+
+    if options.watch {
+        if fix_mode {
+            eprintln!("error: --watch is only supported with `check`");
+            process::exit(2);
+        }
+        run_watch(&crate_root, changed_files.as_deref(), options.config, json_mode, color);
+    }
+
+    let mut config = match rustpeek::config::load(&crate_root) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {e}");
+            process::exit(2);
+        }
+    };
+    config.merge(&options.config);
+
+    let result = rustpeek::analyze_with_config(&crate_root, changed_files.as_deref(), &overlay, &config);
+
+    if fix_mode && options.dry_run {
+        run_fix_dry_run(&crate_root, &config, &options.fix_filter, result, json_mode, color);
+    } else if fix_mode {
+        run_fix(&crate_root, &config, &options.fix_filter, options.interactive, result, json_mode, color);
+    } else if baseline_write {
+        let path = options
+            .baseline
+            .unwrap_or_else(|| crate_root.join(rustpeek::baseline::DEFAULT_BASELINE));
+        run_baseline_write(&crate_root, &path, &result);
+    } else if let Some(path) = &options.baseline {
+        run_check_baseline(&crate_root, path, result, changed_files.as_deref(), format, color);
     } else {
-        run_check(result, json_mode);
+        run_check(&crate_root, result, format, color);
     }
 }
 
 fn print_usage() {
-    eprintln!("Usage: rustpeek [check|fix] [--json] <crate-path> [changed-file ...]");
+    eprintln!("Usage: rustpeek [check|fix] [options] <crate-path> [changed-file ...]");
+    eprintln!("       rustpeek baseline write [options] <crate-path>");
+    eprintln!("       rustpeek lsp|mcp");
     eprintln!();
     eprintln!("Commands:");
     eprintln!("  check   Report errors and suggestions (default)");
     eprintln!("  fix     Auto-apply obvious fixes, report the rest");
+    eprintln!("  baseline write");
+    eprintln!("          Record current diagnostics so `check --baseline` ignores them");
+    eprintln!("  lsp     Run a Language Server Protocol server on stdio");
+    eprintln!("  mcp     Run a Model Context Protocol server on stdio");
     eprintln!();
     eprintln!("Options:");
-    eprintln!("  --json  Output diagnostics as JSON");
+    eprintln!("  --json                 Output diagnostics as JSON (NDJSON per run with --watch)");
+    eprintln!("  --format <format>      Output format: human (default), json, sarif, rustc-json,");
+    eprintln!("                         github or gitlab (all but human and json with `check` only)");
+    eprintln!("  --color <when>         Color output: auto (default), always or never; NO_COLOR is honored");
+    eprintln!("  --dry-run, --diff      fix: print a diff of the fixes instead of applying them;");
+    eprintln!("                         exits with 1 if any fix is pending");
+    eprintln!("  --undo                 fix: revert the last fix run, if its files haven't changed since");
+    eprintln!("  --only <codes>         fix: only fix diagnostics with these codes or rule IDs");
+    eprintln!("  --exclude <codes>      fix: don't fix diagnostics with these codes or rule IDs");
+    eprintln!("  --maybe-incorrect      fix: also apply fixes that may be incorrect, such as typo corrections");
+    eprintln!("  --interactive          fix: show each fix as a diff and ask whether to apply it,");
+    eprintln!("                         including those that may be incorrect");
+    eprintln!("  --watch                Re-check whenever source files or Cargo.toml change");
+    eprintln!("  --changed-since <rev>  Only validate .rs files changed since a git revision");
+    eprintln!("  --staged               Only validate staged .rs files, as staged");
+    eprintln!("  --unstaged             Only validate unstaged and untracked .rs files");
+    eprintln!("  --baseline <file>      Only report diagnostics not in the baseline, and fixed entries");
+    eprintln!("                         (with `baseline write`: where to write it, default");
+    eprintln!("                         rustpeek-baseline.json in the crate root)");
+    eprintln!();
+    eprintln!("Config overrides (see rustpeek.toml):");
+    eprintln!("  --enable <codes>       Only report these codes (comma-separated)");
+    eprintln!("  --disable <codes>      Never report these codes");
+    eprintln!("  --severity CODE=LEVEL  Report CODE as `error` or `suggestion`");
+    eprintln!("  --exclude-path <glob>  Don't validate matching files (relative to the crate root)");
+    eprintln!("  --fixable <codes>      Only apply fixes for these codes");
+    eprintln!("  --features <list>      Enabled features, for #[cfg(feature = \"...\")]");
+    eprintln!("  --cfg <flag>           Enabled cfg flag, e.g. unix or target_os=\"linux\"");
     eprintln!();
     eprintln!("If no changed files are specified, all .rs files are validated.");
+    eprintln!("Changed files may be paths, directories or glob patterns; `fix` only fixes those.");
+}
+
+fn run_check(crate_root: &Path, result: AnalysisResult, format: Format, color: bool) {
+    process::exit(report(crate_root, &result, format, color));
 }
 
-fn run_check(result: AnalysisResult, json_mode: bool) {
+/// Print the result of a check in `format` and return the exit code it warrants.
+fn report(crate_root: &Path, result: &AnalysisResult, format: Format, color: bool) -> i32 {
+    match format {
+        Format::Human => report_check(result, false, color),
+        Format::Json => report_check(result, true, color),
+        Format::Sarif => {
+            let log = rustpeek::sarif::to_sarif(crate_root, result);
+            println!("{}", serde_json::to_string_pretty(&log).unwrap());
+            if result.has_errors() { 1 } else { 0 }
+        }
+        Format::RustcJson => {
+            for message in rustpeek::rustc_json::to_messages(crate_root, result) {
+                println!("{message}");
+            }
+            if result.has_errors() { 1 } else { 0 }
+        }
+        Format::Github => {
+            for annotation in rustpeek::ci::github_annotations(result) {
+                println!("{annotation}");
+            }
+            if result.has_errors() { 1 } else { 0 }
+        }
+        Format::Gitlab => {
+            let report = rustpeek::ci::gitlab_report(crate_root, result);
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            if result.has_errors() { 1 } else { 0 }
+        }
+    }
+}
+
+/// Print the result of a check and return the exit code it warrants.
+fn report_check(result: &AnalysisResult, json_mode: bool, color: bool) -> i32 {
     if json_mode {
-        println!("{}", serde_json::to_string_pretty(&result).unwrap());
-        process::exit(if result.has_errors() { 1 } else { 0 });
+        println!("{}", serde_json::to_string_pretty(result).unwrap());
+        return if result.has_errors() { 1 } else { 0 };
     }
 
     if result.diagnostics.is_empty() {
         println!("rustpeek: no issues found");
-        process::exit(0);
+        return 0;
     }
 
-    print_diagnostics(&result);
+    print_diagnostics(result, color);
 
     let error_count = result.error_count();
     let suggestion_count = result.suggestion_count();
@@ -85,39 +420,189 @@ fn run_check(result: AnalysisResult, json_mode: bool) {
             print!(" ({fixable} auto-fixable, run `rustpeek fix`)");
         }
         println!();
-        process::exit(1);
+        1
     } else {
         print!("rustpeek: {suggestion_count} suggestion(s)");
         if fixable > 0 {
             print!(" ({fixable} auto-fixable, run `rustpeek fix`)");
         }
         println!();
-        process::exit(0);
+        0
     }
 }
 
-fn run_fix(result: AnalysisResult, json_mode: bool) {
-    let apply_result = rustpeek::fixer::apply_fixes(&result);
+fn run_baseline_write(crate_root: &Path, path: &Path, result: &AnalysisResult) {
+    let baseline = rustpeek::baseline::from_result(crate_root, result);
+    if let Err(e) = rustpeek::baseline::write(path, &baseline) {
+        eprintln!("error: {e}");
+        process::exit(2);
+    }
+    println!(
+        "rustpeek: wrote {} baseline entr{} to {}",
+        baseline.entries.len(),
+        if baseline.entries.len() == 1 { "y" } else { "ies" },
+        path.display()
+    );
+    process::exit(0);
+}
+
+fn run_check_baseline(
+    crate_root: &Path,
+    path: &Path,
+    result: AnalysisResult,
+    changed_files: Option<&[PathBuf]>,
+    format: Format,
+    color: bool,
+) {
+    let baseline = match rustpeek::baseline::load(path) {
+        Ok(baseline) => baseline,
+        Err(e) => {
+            eprintln!("error: {e}");
+            process::exit(2);
+        }
+    };
+
+    // Only entries in files that were actually checked can be known to be fixed
+    let checked = changed_files.map(|changed| {
+        let crate_files = rustpeek::discovery::discover_crate(crate_root);
+        rustpeek::discovery::match_changed_files(&crate_files, changed).files
+    });
+    let comparison = rustpeek::baseline::compare(&baseline, crate_root, result, checked.as_deref());
+
+    if format == Format::Json {
+        let output = serde_json::json!({
+            "diagnostics": comparison.new.diagnostics,
+            "baseline_fixed": comparison.fixed,
+        });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        process::exit(if comparison.new.has_errors() { 1 } else { 0 });
+    }
+
+    // Only human output has room for fixed entries
+    let code = report(crate_root, &comparison.new, format, color);
+    if format == Format::Human && !comparison.fixed.is_empty() {
+        println!();
+        println!(
+            "rustpeek: {} baseline entr{} no longer reported:",
+            comparison.fixed.len(),
+            if comparison.fixed.len() == 1 { "y is" } else { "ies are" }
+        );
+        for entry in &comparison.fixed {
+            let code = entry.code.as_deref().map(|c| format!("[{c}] ")).unwrap_or_default();
+            println!("  fixed: {}: {code}{}", entry.file, entry.message);
+        }
+        println!("rustpeek: run `rustpeek baseline write` to update {}", path.display());
+    }
+    process::exit(code);
+}
+
+fn run_watch(
+    crate_root: &Path,
+    changed_files: Option<&[PathBuf]>,
+    overrides: Config,
+    json_mode: bool,
+    color: bool,
+) -> ! {
+    let watched = rustpeek::watch::watch(crate_root, changed_files, overrides, |run| {
+        if json_mode {
+            let event = serde_json::json!({
+                "event": "run",
+                "run": run.run,
+                "changed": run.changed,
+                "rediscovered": run.rediscovered,
+                "errors": run.result.error_count(),
+                "suggestions": run.result.suggestion_count(),
+                "diagnostics": run.result.diagnostics,
+            });
+            println!("{event}");
+            return;
+        }
+
+        // Clear the screen and move the cursor home before reprinting
+        print!("\x1B[2J\x1B[H");
+        if run.rediscovered {
+            println!("rustpeek: Cargo.toml changed, re-indexed crate (run {})", run.run);
+        } else if !run.changed.is_empty() {
+            let changed: Vec<String> = run.changed.iter().map(|p| p.display().to_string()).collect();
+            println!("rustpeek: {} changed (run {})", changed.join(", "), run.run);
+        }
+        report_check(run.result, false, color);
+        println!("rustpeek: watching {} for changes...", crate_root.display());
+    });
+
+    if let Err(e) = watched {
+        eprintln!("error: watch failed: {e}");
+    }
+    process::exit(2);
+}
+
+fn run_fix(
+    crate_root: &Path,
+    config: &Config,
+    filter: &FixFilter,
+    interactive: bool,
+    result: AnalysisResult,
+    json_mode: bool,
+    color: bool,
+) {
+    // Each fix is reviewed, so those that may be incorrect are offered too
+    let filter = &FixFilter { maybe_incorrect: filter.maybe_incorrect || interactive, ..filter.clone() };
+    let mut plan = rustpeek::fixer::plan_selected_fixes(crate_root, config, &result, |d| filter.allows(d));
+    if interactive {
+        print_rejected(&plan.rejected);
+        let accepted = prompt_fixes(crate_root, &plan, color);
+        // Verify again without the skipped fixes, which later ones may have relied on
+        if accepted.len() < plan.fixes.len() {
+            let accepted = |diag: &rustpeek::types::Diagnostic| {
+                diag.fix.as_ref().is_some_and(|fix| accepted.iter().any(|a| a.edits == fix.edits))
+            };
+            plan = rustpeek::fixer::plan_selected_fixes(crate_root, config, &result, accepted);
+        } else {
+            plan.rejected.clear();
+        }
+    }
+    let apply_result = rustpeek::fixer::apply_plan(crate_root, plan, &result);
+    // Files that couldn't be rewritten are a failure of rustpeek, not of the crate
+    let exit_code = |remaining: &AnalysisResult| {
+        if !apply_result.write_errors.is_empty() {
+            2
+        } else if remaining.has_errors() {
+            1
+        } else {
+            0
+        }
+    };
 
     if json_mode {
         let output = serde_json::json!({
             "fixes_applied": apply_result.fixes_applied,
+            "rejected": apply_result.rejected,
+            "deferred": apply_result.deferred,
+            "write_errors": apply_result.write_errors,
+            "journal": apply_result.journal,
             "remaining": apply_result.remaining,
         });
         println!("{}", serde_json::to_string_pretty(&output).unwrap());
-        process::exit(if apply_result.remaining.has_errors() { 1 } else { 0 });
+        process::exit(exit_code(&apply_result.remaining));
+    }
+
+    for error in &apply_result.write_errors {
+        eprintln!("error: {error}");
     }
 
     if apply_result.fixes_applied > 0 {
         println!("rustpeek: applied {} fix(es)", apply_result.fixes_applied);
         // Show what was fixed
-        for diag in &result.diagnostics {
-            if let Some(fix) = &diag.fix {
-                println!("  fixed: {fix}");
-            }
+        for fix in &apply_result.applied {
+            println!("  fixed: {fix}");
+        }
+        if apply_result.journal.is_some() {
+            println!("  (run `rustpeek fix --undo` to revert)");
         }
         println!();
     }
+    print_rejected(&apply_result.rejected);
+    print_deferred(&apply_result.deferred);
 
     let remaining = &apply_result.remaining;
     if remaining.diagnostics.is_empty() {
@@ -126,24 +611,209 @@ fn run_fix(result: AnalysisResult, json_mode: bool) {
         } else {
             println!("rustpeek: all issues fixed");
         }
-        process::exit(0);
+        process::exit(exit_code(remaining));
     }
 
-    print_diagnostics(remaining);
+    // Show the rewritten files as they were, which is what the spans refer to
+    let mut renderer = Renderer::new(color);
+    for change in &apply_result.files {
+        renderer.set_source(&change.path, &change.before);
+    }
+    print_diagnostics_with(&mut renderer, remaining);
 
+    print_maybe_incorrect(remaining, filter);
     let error_count = remaining.error_count();
     let suggestion_count = remaining.suggestion_count();
 
     if error_count > 0 {
         println!("rustpeek: {error_count} remaining error(s), {suggestion_count} suggestion(s)");
-        process::exit(1);
     } else {
         println!("rustpeek: {suggestion_count} remaining suggestion(s)");
-        process::exit(0);
     }
+    process::exit(exit_code(remaining));
+}
+
+/// Print the fixes `fix` would apply, as a unified diff per file or as JSON
+/// edits, and exit with 1 if there are any.
+fn run_fix_dry_run(
+    crate_root: &Path,
+    config: &Config,
+    filter: &FixFilter,
+    result: AnalysisResult,
+    json_mode: bool,
+    color: bool,
+) {
+    let plan = rustpeek::fixer::plan_selected_fixes(crate_root, config, &result, |d| filter.allows(d));
+    let exit_code = if !plan.errors.is_empty() {
+        2
+    } else if plan.fixes.is_empty() {
+        0
+    } else {
+        1
+    };
+
+    if json_mode {
+        let output = serde_json::json!({
+            "dry_run": true,
+            "fixes_pending": plan.fixes.len(),
+            "fixes": plan.fixes,
+            "rejected": plan.rejected,
+            "deferred": plan.deferred,
+            "read_errors": plan.errors,
+            "remaining": plan.remaining(&result),
+        });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        process::exit(exit_code);
+    }
+
+    for error in &plan.errors {
+        eprintln!("error: {error}");
+    }
+
+    let renderer = Renderer::new(color);
+    for change in &plan.files {
+        print!("{}", renderer.render_diff(&change.diff(crate_root)));
+    }
+
+    if !plan.files.is_empty() {
+        println!();
+    }
+    print_rejected(&plan.rejected);
+    print_deferred(&plan.deferred);
+    print_maybe_incorrect(&plan.remaining(&result), filter);
+    if plan.fixes.is_empty() {
+        println!("rustpeek: no fixes to apply");
+    } else {
+        println!("rustpeek: {} fix(es) would be applied (run `rustpeek fix` to apply them)", plan.fixes.len());
+    }
+    process::exit(exit_code);
+}
+
+/// Show each planned fix as a diff and ask on stdin whether to apply it.
+/// Returns the fixes the user accepted; end of input counts as `q`.
+fn prompt_fixes(crate_root: &Path, plan: &FixPlan, color: bool) -> Vec<Fix> {
+    let renderer = Renderer::new(color);
+    let mut stdin = std::io::stdin().lock();
+    let mut accepted = Vec::new();
+    let mut accept_rest = false;
+
+    for (i, fix) in plan.fixes.iter().enumerate() {
+        if accept_rest {
+            accepted.push(fix.clone());
+            continue;
+        }
+        let review = if fix.applicability == Applicability::MaybeIncorrect { " (may be incorrect)" } else { "" };
+        println!("[{}/{}] {fix}{review}", i + 1, plan.fixes.len());
+        for change in plan.preview(fix) {
+            print!("{}", renderer.render_diff(&change.diff(crate_root)));
+        }
+
+        loop {
+            print!("Apply this fix [y,n,a,q,?]? ");
+            let _ = std::io::stdout().flush();
+            let mut answer = String::new();
+            if stdin.read_line(&mut answer).unwrap_or(0) == 0 {
+                println!();
+                return accepted;
+            }
+            match answer.trim().to_ascii_lowercase().as_str() {
+                "y" => accepted.push(fix.clone()),
+                "n" => {}
+                "a" => {
+                    accepted.push(fix.clone());
+                    accept_rest = true;
+                }
+                "q" => return accepted,
+                _ => {
+                    println!("y - apply this fix");
+                    println!("n - don't apply this fix");
+                    println!("a - apply this fix and all remaining ones");
+                    println!("q - don't apply this fix or any remaining ones");
+                    continue;
+                }
+            }
+            break;
+        }
+        println!();
+    }
+    accepted
+}
+
+/// Revert the most recent fix run from the crate's journal.
+fn run_fix_undo(crate_root: &Path, json_mode: bool) {
+    let undone = rustpeek::journal::undo(crate_root);
+    if json_mode {
+        let output = match &undone {
+            Ok(undone) => serde_json::json!({ "reverted": undone.files, "journal": undone.entry }),
+            Err(e) => serde_json::json!({ "error": e }),
+        };
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        process::exit(if undone.is_ok() { 0 } else { 2 });
+    }
+
+    match undone {
+        Ok(undone) => {
+            println!("rustpeek: reverted {} file(s) to before the last fix run", undone.files.len());
+            for file in &undone.files {
+                println!("  reverted: {file}");
+            }
+            process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            process::exit(2);
+        }
+    }
+}
+
+/// List fixes that were left out because re-analysis showed they didn't help.
+fn print_rejected(rejected: &[rustpeek::fixer::RejectedFix]) {
+    if rejected.is_empty() {
+        return;
+    }
+    println!("rustpeek: rejected {} fix(es) that would not improve the crate", rejected.len());
+    for rejected in rejected {
+        println!("  rejected: {} ({})", rejected.fix, rejected.reason);
+    }
+    println!();
+}
+
+/// List fixes that were left for the next run because they conflict with another.
+fn print_deferred(deferred: &[rustpeek::fixer::RejectedFix]) {
+    if deferred.is_empty() {
+        return;
+    }
+    println!("rustpeek: deferred {} conflicting fix(es) to the next run", deferred.len());
+    for deferred in deferred {
+        println!("  deferred: {} ({})", deferred.fix, deferred.reason);
+    }
+    println!();
+}
+
+/// Count the remaining fixes `filter` left out only because they may be
+/// incorrect, and say how to apply them.
+fn print_maybe_incorrect(remaining: &AnalysisResult, filter: &FixFilter) {
+    if filter.maybe_incorrect {
+        return;
+    }
+    let opted_in = FixFilter { maybe_incorrect: true, ..filter.clone() };
+    let count = remaining
+        .diagnostics
+        .iter()
+        .filter(|d| opted_in.allows(d) && !filter.allows(d))
+        .count();
+    if count > 0 {
+        println!(
+            "rustpeek: left {count} fix(es) that may be incorrect for review (apply them with `--interactive` or `--maybe-incorrect`)"
+        );
+    }
+}
+
+fn print_diagnostics(result: &AnalysisResult, color: bool) {
+    print_diagnostics_with(&mut Renderer::new(color), result);
 }
 
-fn print_diagnostics(result: &AnalysisResult) {
+fn print_diagnostics_with(renderer: &mut Renderer, result: &AnalysisResult) {
     let mut errors: Vec<_> = result
         .diagnostics
         .iter()
@@ -155,16 +825,16 @@ fn print_diagnostics(result: &AnalysisResult) {
         .filter(|d| d.severity == Severity::Suggestion)
         .collect();
 
-    errors.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
-    suggestions.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+    errors.sort_by(|a, b| (a.file(), a.span.start).cmp(&(b.file(), b.span.start)));
+    suggestions.sort_by(|a, b| (a.file(), a.span.start).cmp(&(b.file(), b.span.start)));
 
     for diag in &errors {
-        println!("{diag}");
+        println!("{}", renderer.render(diag));
         println!();
     }
 
     for diag in &suggestions {
-        println!("{diag}");
+        println!("{}", renderer.render(diag));
         println!();
     }
 }
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::{Value, json};
+
+use crate::types::*;
+use crate::{config, discovery, fixer, rules};
+
+/// Protocol version reported when the client doesn't ask for one.
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Run the MCP server on stdin/stdout until stdin closes.
+///
+/// Messages are newline-delimited JSON-RPC, per the MCP stdio transport.
+pub fn run() -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let message: Value = match serde_json::from_str(&line) {
+            Ok(m) => m,
+            Err(e) => {
+                send(&mut out, &json!({
+                    "jsonrpc": "2.0",
+                    "id": Value::Null,
+                    "error": { "code": -32700, "message": format!("parse error: {e}") },
+                }))?;
+                continue;
+            }
+        };
+
+        // Notifications (no id) never get a response
+        let Some(id) = message.get("id").cloned() else {
+            continue;
+        };
+
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+        let response = match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": params["protocolVersion"].as_str().unwrap_or(PROTOCOL_VERSION),
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "rustpeek", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+            "tools/call" => Ok(call_tool(
+                params["name"].as_str().unwrap_or(""),
+                &params["arguments"],
+            )),
+            _ => Err(json!({ "code": -32601, "message": format!("method not found: `{method}`") })),
+        };
+
+        let reply = match response {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+        };
+        send(&mut out, &reply)?;
+        // Nothing parsed for this call outlives it, so neither need the
+        // locations proc_macro2 recorded for its spans
+        proc_macro2::extra::invalidate_current_thread_spans();
+    }
+
+    Ok(())
+}
+
+fn send(out: &mut impl Write, message: &Value) -> io::Result<()> {
+    writeln!(out, "{}", serde_json::to_string(message)?)?;
+    out.flush()
+}
+
+/// JSON schemas for every tool the server exposes.
+fn tool_definitions() -> Value {
+    let crate_path = json!({
+        "type": "string",
+        "description": "Path to the crate root (the directory containing Cargo.toml)",
+    });
+    let changed_files = json!({
+        "type": "array",
+        "items": { "type": "string" },
+        "description": "Only validate these files (relative to crate_path or absolute). Omit to validate every file.",
+    });
+
+    json!([
+        {
+            "name": "check",
+            "description": "Validate a Rust crate without compiling it. Returns diagnostics for unresolved imports, missing modules, struct fields, enum variants, argument counts, visibility and missing imports.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "crate_path": crate_path, "changed_files": changed_files },
+                "required": ["crate_path"],
+            },
+        },
+        {
+            "name": "fix",
+            "description": "Compute the auto-fixes for a crate (missing imports; with maybe_incorrect, import typos too). With dry_run (the default) nothing is written and a unified diff of the changes is returned; otherwise the fixes are applied to disk.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "crate_path": crate_path,
+                    "changed_files": changed_files,
+                    "dry_run": { "type": "boolean", "description": "Only report fixes, don't write files (default: true)" },
+                    "only": { "type": "array", "items": { "type": "string" }, "description": "Only fix diagnostics with these error codes or rule IDs, e.g. [\"E0412\"]" },
+                    "exclude": { "type": "array", "items": { "type": "string" }, "description": "Never fix diagnostics with these error codes or rule IDs" },
+                    "maybe_incorrect": { "type": "boolean", "description": "Also apply fixes that may be incorrect, such as typo corrections (default: false)" },
+                },
+                "required": ["crate_path"],
+            },
+        },
+        {
+            "name": "find_symbol",
+            "description": "Find every item with the given name in the crate: its kind, visibility, defining module, file and line.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "crate_path": crate_path,
+                    "name": { "type": "string", "description": "Item name, e.g. `User`" },
+                },
+                "required": ["crate_path", "name"],
+            },
+        },
+        {
+            "name": "list_module",
+            "description": "List the items, imports, impl methods and child modules of a module.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "crate_path": crate_path,
+                    "module": { "type": "string", "description": "Module path, e.g. `crate::db::models` (default: `crate`)" },
+                },
+                "required": ["crate_path"],
+            },
+        },
+        {
+            "name": "explain_code",
+            "description": "Explain a diagnostic code or rule ID reported by rustpeek, e.g. `E0432` or `unresolved-import`.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "code": { "type": "string" } },
+                "required": ["code"],
+            },
+        },
+    ])
+}
+
+/// Dispatch a `tools/call` request. Tool failures are reported in the result, not as protocol errors.
+fn call_tool(name: &str, args: &Value) -> Value {
+    let result = match name {
+        "check" => tool_check(args),
+        "fix" => tool_fix(args),
+        "find_symbol" => tool_find_symbol(args),
+        "list_module" => tool_list_module(args),
+        "explain_code" => tool_explain_code(args),
+        _ => Err(format!("unknown tool `{name}`")),
+    };
+
+    match result {
+        Ok(value) => json!({
+            "content": [{ "type": "text", "text": serde_json::to_string_pretty(&value).unwrap_or_default() }],
+            "structuredContent": value,
+            "isError": false,
+        }),
+        Err(message) => json!({
+            "content": [{ "type": "text", "text": message }],
+            "isError": true,
+        }),
+    }
+}
+
+fn tool_check(args: &Value) -> Result<Value, String> {
+    let crate_root = crate_root_arg(args)?;
+    let changed = changed_files_arg(args, &crate_root);
+    let result = crate::analyze(&crate_root, changed.as_deref());
+    Ok(json!({
+        "errors": result.error_count(),
+        "suggestions": result.suggestion_count(),
+        "fixable": result.fixable_count(),
+        "diagnostics": result.diagnostics,
+    }))
+}
+
+fn tool_fix(args: &Value) -> Result<Value, String> {
+    let crate_root = crate_root_arg(args)?;
+    let changed = changed_files_arg(args, &crate_root);
+    let dry_run = args["dry_run"].as_bool().unwrap_or(true);
+    let filter = fixer::FixFilter {
+        only: args["only"].as_array().map(|codes| string_items(codes)),
+        exclude: args["exclude"].as_array().map(|codes| string_items(codes)).unwrap_or_default(),
+        maybe_incorrect: args["maybe_incorrect"].as_bool().unwrap_or(false),
+    };
+    filter.validate()?;
+    let result = crate::analyze(&crate_root, changed.as_deref());
+    // An invalid config is reported by `analyze`, and leaves no fixes to verify
+    let config = config::load(&crate_root).unwrap_or_default();
+    let plan = fixer::plan_selected_fixes(&crate_root, &config, &result, |d| filter.allows(d));
+
+    if dry_run {
+        let diff: String = plan.files.iter().map(|change| change.diff(&crate_root)).collect();
+        return Ok(json!({
+            "dry_run": true,
+            "fixes": plan.fixes,
+            "diff": diff,
+            "rejected": plan.rejected,
+            "deferred": plan.deferred,
+            "read_errors": plan.errors,
+            "unfixable": plan.remaining(&result).diagnostics,
+        }));
+    }
+
+    let applied = fixer::apply_plan(&crate_root, plan, &result);
+    Ok(json!({
+        "dry_run": false,
+        "fixes_applied": applied.fixes_applied,
+        "fixes": applied.applied,
+        "rejected": applied.rejected,
+        "deferred": applied.deferred,
+        "write_errors": applied.write_errors,
+        "remaining": applied.remaining.diagnostics,
+    }))
+}
+
+fn tool_find_symbol(args: &Value) -> Result<Value, String> {
+    let crate_root = crate_root_arg(args)?;
+    let name = args["name"].as_str().ok_or("missing `name` argument")?;
+    let symbols = symbol_table(&crate_root);
+
+    let mut matches: Vec<Value> = symbols
+        .modules
+        .values()
+        .flat_map(|m| m.items.iter().map(move |item| (m, item)))
+        .filter(|(_, item)| item.name == name)
+        .map(|(m, item)| json!({ "file": m.file_path, "item": item }))
+        .collect();
+    matches.sort_by_key(|v| v["item"]["module"].as_str().unwrap_or("").to_string());
+
+    let methods: Vec<Value> = symbols
+        .modules
+        .iter()
+        .flat_map(|(path, m)| m.impls.iter().map(move |imp| (path, m, imp)))
+        .flat_map(|(path, m, imp)| {
+            imp.methods.iter().filter(|method| method.name == name).map(move |method| {
+                json!({ "module": path, "file": m.file_path, "type_name": imp.type_name, "method": method })
+            })
+        })
+        .collect();
+
+    Ok(json!({ "items": matches, "methods": methods }))
+}
+
+fn tool_list_module(args: &Value) -> Result<Value, String> {
+    let crate_root = crate_root_arg(args)?;
+    let module = args["module"].as_str().unwrap_or("crate");
+    let module_path = ModulePath(module.split("::").map(String::from).collect());
+    let symbols = symbol_table(&crate_root);
+
+    let info = symbols
+        .modules
+        .get(&module_path)
+        .ok_or_else(|| format!("module `{module}` not found"))?;
+    Ok(json!({ "module": module_path, "info": info }))
+}
+
+fn tool_explain_code(args: &Value) -> Result<Value, String> {
+    let code = args["code"].as_str().ok_or("missing `code` argument")?;
+    let rule = rules::lookup(code).ok_or_else(|| format!("rustpeek doesn't report `{code}`"))?;
+    Ok(json!({ "id": rule.id, "code": rule.code, "summary": rule.summary, "explanation": rule.explanation, "lints": rule.lints }))
+}
+
+fn crate_root_arg(args: &Value) -> Result<PathBuf, String> {
+    let path = args["crate_path"].as_str().ok_or("missing `crate_path` argument")?;
+    let root = PathBuf::from(path);
+    if !root.join("Cargo.toml").exists() {
+        return Err(format!("no Cargo.toml found in {}", root.display()));
+    }
+    Ok(root)
+}
+
+/// Changed files from the arguments, with relative paths resolved against the crate root.
+fn string_items(values: &[Value]) -> Vec<String> {
+    values.iter().filter_map(|v| v.as_str()).map(String::from).collect()
+}
+
+fn changed_files_arg(args: &Value, crate_root: &Path) -> Option<Vec<PathBuf>> {
+    let files = args["changed_files"].as_array()?;
+    Some(
+        files
+            .iter()
+            .filter_map(|f| f.as_str())
+            .map(|f| {
+                let path = Path::new(f);
+                if path.is_absolute() || path.starts_with(crate_root) {
+                    path.to_path_buf()
+                } else {
+                    crate_root.join(path)
+                }
+            })
+            .collect(),
+    )
+}
+
+fn symbol_table(crate_root: &Path) -> SymbolTable {
+    let crate_files = discovery::discover_crate(crate_root);
+    let config = config::load(crate_root).unwrap_or_default();
+    crate::build_symbol_table(&crate_files, &HashMap::new(), &config)
+}
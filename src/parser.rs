@@ -1,23 +1,14 @@
 use std::path::Path;
 
-use crate::types::{Diagnostic, Severity};
+use crate::types::{Diagnostic, Span};
 
 /// Pass 1: Parse a file with syn and return syntax errors if any.
 pub fn check_syntax(file_path: &Path, source: &str) -> Vec<Diagnostic> {
     match syn::parse_file(source) {
         Ok(_) => Vec::new(),
         Err(err) => {
-            let span = err.span();
-            vec![Diagnostic {
-                severity: Severity::Error,
-                file: file_path.to_path_buf(),
-                line: span.start().line,
-                column: span.start().column + 1,
-                message: format!("syntax error: {err}"),
-                error_code: None,
-                hint: None,
-                fix: None,
-            }]
+            let span = Span::from_syn(file_path, err.span());
+            vec![Diagnostic::error("syntax-error", span, format!("syntax error: {err}"))]
         }
     }
 }
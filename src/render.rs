@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use crate::types::*;
+
+/// When to color terminal output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color if stdout is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    /// Whether output to stdout should carry ANSI colors.
+    pub fn use_color(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+// ANSI SGR codes, matching rustc's palette
+const BOLD: &str = "1";
+const ERROR: &str = "1;31";
+const SUGGESTION: &str = "1;33";
+const GUTTER: &str = "1;34";
+const LABEL: &str = "1;34";
+const HELP: &str = "1;36";
+const ADDED: &str = "32";
+const REMOVED: &str = "31";
+
+/// Renders diagnostics like rustc: a header, then the source lines they
+/// point at with the spans underlined, then notes, help and the fix as a diff.
+pub struct Renderer {
+    color: bool,
+    /// Files read so far; None if a file couldn't be read
+    sources: HashMap<PathBuf, Option<SourceFile>>,
+}
+
+struct SourceFile {
+    text: String,
+    lines: Vec<String>,
+}
+
+/// A line of a fix diff: (line number, `+` or `-`, text).
+type DiffLine = (usize, char, String);
+
+/// An underlined span on one source line.
+struct Annotation {
+    line: usize,
+    /// 1-indexed character columns, end exclusive
+    start: usize,
+    end: usize,
+    primary: bool,
+    message: Option<String>,
+}
+
+impl Renderer {
+    pub fn new(color: bool) -> Self {
+        Renderer {
+            color,
+            sources: HashMap::new(),
+        }
+    }
+
+    /// Show `file` as `text` instead of reading it, e.g. as it was before `fix`
+    /// rewrote it, which is what the spans of diagnostics from before refer to.
+    pub fn set_source(&mut self, file: &Path, text: &str) {
+        let text = crate::strip_bom(text).to_string();
+        self.sources.insert(
+            file.to_path_buf(),
+            Some(SourceFile {
+                lines: text.lines().map(String::from).collect(),
+                text,
+            }),
+        );
+    }
+
+    pub fn render(&mut self, diag: &Diagnostic) -> String {
+        let (severity, level_style) = match diag.severity {
+            Severity::Error => ("error", ERROR),
+            Severity::Suggestion => ("suggestion", SUGGESTION),
+        };
+        let code = diag.error_code.as_deref().map(|c| format!("[{c}]")).unwrap_or_default();
+
+        // Group annotations by file, primary file first, in order of appearance
+        let mut groups: Vec<(&Span, Vec<Annotation>)> = vec![(&diag.span, Vec::new())];
+        if diag.span.start.line > 0 {
+            groups[0].1.push(self.annotation(&diag.span, true, None));
+        }
+        for label in &diag.labels {
+            let annotation = (label.span.start.line > 0)
+                .then(|| self.annotation(&label.span, false, Some(label.message.clone())));
+            match groups.iter_mut().find(|(span, _)| span.file == label.span.file) {
+                Some((_, annotations)) => annotations.extend(annotation),
+                None => groups.push((&label.span, annotation.into_iter().collect())),
+            }
+        }
+
+        let fix_files = diag.fix.as_ref().map(|fix| self.fix_lines(fix)).unwrap_or_default();
+        let max_line = groups
+            .iter()
+            .flat_map(|(_, annotations)| annotations.iter().map(|a| a.line))
+            .chain(fix_files.iter().flat_map(|(_, lines)| lines.iter().map(|(line, _, _)| *line)))
+            .max()
+            .unwrap_or(0);
+        let width = max_line.to_string().len();
+        let blank_gutter = self.paint(GUTTER, &format!("{:width$} |", ""));
+
+        let mut out = format!(
+            "{}{}",
+            self.paint(level_style, &format!("{severity}{code}")),
+            self.paint(BOLD, &format!(": {}", diag.message))
+        );
+
+        for (i, (span, annotations)) in groups.iter_mut().enumerate() {
+            let arrow = if i == 0 { "-->" } else { ":::" };
+            if i > 0 {
+                out.push_str(&format!("\n{blank_gutter}"));
+            }
+            let location = if span.start.line == 0 {
+                span.file.display().to_string()
+            } else {
+                format!("{}:{}:{}", span.file.display(), span.start.line, span.start.column)
+            };
+            out.push_str(&format!("\n{:width$}{} {location}", "", self.paint(GUTTER, arrow)));
+            if annotations.is_empty() {
+                continue;
+            }
+            let Some(lines) = self.lines(&span.file).cloned() else {
+                continue;
+            };
+
+            annotations.sort_by_key(|a| (a.line, a.start));
+            out.push_str(&format!("\n{blank_gutter}"));
+            let mut previous_line = None;
+            for annotation in annotations.iter() {
+                if previous_line != Some(annotation.line) {
+                    if previous_line.is_some_and(|prev| annotation.line > prev + 1) {
+                        out.push_str(&format!("\n{}", self.paint(GUTTER, "...")));
+                    }
+                    let text = lines.get(annotation.line - 1).map(String::as_str).unwrap_or("");
+                    out.push_str(&format!(
+                        "\n{} {text}",
+                        self.paint(GUTTER, &format!("{:>width$} |", annotation.line))
+                    ));
+                    previous_line = Some(annotation.line);
+                }
+                let text = lines.get(annotation.line - 1).map(String::as_str).unwrap_or("");
+                out.push_str(&format!("\n{blank_gutter} {}", self.underline(text, annotation, level_style)));
+            }
+        }
+
+        if !diag.notes.is_empty() || !diag.help.is_empty() {
+            out.push_str(&format!("\n{blank_gutter}"));
+        }
+        for note in &diag.notes {
+            out.push_str(&format!("\n{:width$} {} {note}", "", self.paint(BOLD, "= note:")));
+        }
+        for help in &diag.help {
+            out.push_str(&format!("\n{:width$} {} {help}", "", self.paint(BOLD, "= help:")));
+        }
+
+        if !fix_files.is_empty() {
+            out.push_str(&format!("\n{}", self.paint(HELP, "help: try")));
+        }
+        for (file, fix_lines) in &fix_files {
+            if *file != diag.file() {
+                out.push_str(&format!("\n{:width$}{} {}", "", self.paint(GUTTER, "-->"), file.display()));
+            }
+            out.push_str(&format!("\n{blank_gutter}"));
+            for (line, marker, text) in fix_lines {
+                let style = if *marker == '+' { ADDED } else { REMOVED };
+                out.push_str(&format!(
+                    "\n{} {}",
+                    self.paint(GUTTER, &format!("{line:>width$} |")),
+                    self.paint(style, &format!("{marker} {text}"))
+                ));
+            }
+            out.push_str(&format!("\n{blank_gutter}"));
+        }
+
+        out
+    }
+
+    /// Color a unified diff: headers bold, hunk headers like help, and added
+    /// and removed lines like the fix diffs of diagnostics.
+    pub fn render_diff(&self, diff: &str) -> String {
+        // Lines keep any `\r`, so diffs of CRLF files still apply
+        diff.split_inclusive('\n')
+            .map(|line| line.strip_suffix('\n').unwrap_or(line))
+            .map(|line| {
+                let style = if line.starts_with("---") || line.starts_with("+++") {
+                    BOLD
+                } else if line.starts_with("@@") {
+                    HELP
+                } else if line.starts_with('+') {
+                    ADDED
+                } else if line.starts_with('-') {
+                    REMOVED
+                } else {
+                    return line.to_string();
+                };
+                self.paint(style, line)
+            })
+            .map(|line| line + "\n")
+            .collect()
+    }
+
+    fn annotation(&mut self, span: &Span, primary: bool, message: Option<String>) -> Annotation {
+        let end = if span.end.line == span.start.line && span.end.column > span.start.column {
+            span.end.column
+        } else {
+            // Multi-line or empty span: underline to the end of the first line
+            let line_len = self
+                .lines(&span.file)
+                .and_then(|lines| lines.get(span.start.line.saturating_sub(1)))
+                .map_or(0, |l| l.chars().count());
+            (line_len + 1).max(span.start.column + 1)
+        };
+        Annotation {
+            line: span.start.line,
+            start: span.start.column.max(1),
+            end,
+            primary,
+            message,
+        }
+    }
+
+    /// The underline row for an annotation, keeping tabs so it lines up with the source.
+    fn underline(&self, text: &str, annotation: &Annotation, level_style: &str) -> String {
+        let indent: String = text
+            .chars()
+            .take(annotation.start - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let (mark, style) = if annotation.primary { ('^', level_style) } else { ('-', LABEL) };
+        let mut marks: String = std::iter::repeat_n(mark, annotation.end - annotation.start).collect();
+        if let Some(message) = &annotation.message {
+            marks.push(' ');
+            marks.push_str(message);
+        }
+        format!("{indent}{}", self.paint(style, &marks))
+    }
+
+    /// The fix as diff lines, per file.
+    fn fix_lines<'f>(&mut self, fix: &'f Fix) -> Vec<(&'f Path, Vec<DiffLine>)> {
+        let mut edits: Vec<&TextEdit> = fix.edits.iter().collect();
+        edits.sort_by_key(|edit| (&edit.file, edit.byte_range.start));
+
+        let mut files: Vec<(&Path, Vec<DiffLine>)> = Vec::new();
+        for edit in edits {
+            let Some(text) = self.source(&edit.file).map(|source| source.text.clone()) else {
+                continue;
+            };
+            let lines = edit_lines(&text, edit);
+            match files.last_mut() {
+                Some((file, file_lines)) if *file == edit.file => file_lines.extend(lines),
+                _ => files.push((&edit.file, lines)),
+            }
+        }
+        files
+    }
+
+    fn lines(&mut self, file: &Path) -> Option<&Vec<String>> {
+        self.source(file).map(|source| &source.lines)
+    }
+
+    fn source(&mut self, file: &Path) -> Option<&SourceFile> {
+        self.sources
+            .entry(file.to_path_buf())
+            .or_insert_with(|| {
+                crate::read_file(file).ok().map(|text| SourceFile {
+                    lines: text.lines().map(String::from).collect(),
+                    text,
+                })
+            })
+            .as_ref()
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{style}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Diff lines for one edit of `text`. Whole-line insertions and deletions
+/// show only the lines added or removed; other edits show the lines they
+/// touch before and after.
+fn edit_lines(text: &str, edit: &TextEdit) -> Vec<DiffLine> {
+    let start = char_boundary(text, edit.byte_range.start);
+    let range = start..char_boundary(text, edit.byte_range.end).max(start);
+    let start = Position::at_byte(text, range.start);
+    let at_line_start = |byte: usize| byte == 0 || text[..byte].ends_with('\n');
+
+    if range.is_empty() && at_line_start(range.start) && edit.replacement.ends_with('\n') {
+        return edit.replacement.lines().enumerate().map(|(i, line)| (start.line + i, '+', line.to_string())).collect();
+    }
+    if edit.replacement.is_empty() && at_line_start(range.start) && (at_line_start(range.end) || range.end == text.len()) {
+        return text[range.clone()].lines().enumerate().map(|(i, line)| (start.line + i, '-', line.to_string())).collect();
+    }
+
+    let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = text[range.end..].find('\n').map_or(text.len(), |i| range.end + i);
+    let after = format!("{}{}{}", &text[line_start..range.start], edit.replacement, &text[range.end..line_end]);
+    let removed = text[line_start..line_end].lines().enumerate().map(|(i, line)| (start.line + i, '-', line.to_string()));
+    let added = after.lines().enumerate().map(|(i, line)| (start.line + i, '+', line.to_string()));
+    removed.chain(added).collect()
+}
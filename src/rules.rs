@@ -0,0 +1,179 @@
+use crate::types::Severity;
+
+/// A check rustpeek performs.
+#[derive(Debug)]
+pub struct Rule {
+    /// Stable rustpeek rule ID, e.g. `unresolved-import`
+    pub id: &'static str,
+    /// The rustc error code it mirrors, e.g. `E0432`, if any
+    pub code: Option<&'static str>,
+    /// Severity it reports with unless configured otherwise
+    pub severity: Severity,
+    /// One-line description of what the check reports
+    pub summary: &'static str,
+    /// Longer explanation with an example and how to fix it
+    pub explanation: &'static str,
+    /// rustc lints whose `#[allow(...)]` also silences it
+    pub lints: &'static [&'static str],
+}
+
+/// Every check rustpeek can report.
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "missing-module",
+        code: Some("E0583"),
+        severity: Severity::Error,
+        summary: "file not found for module",
+        explanation: "A `mod foo;` declaration has no matching source file.\n\n\
+            For `mod foo;` in `src/lib.rs`, rustc looks for `src/foo.rs` or `src/foo/mod.rs`.\n\
+            Create the file, fix the module name, or declare the module inline with `mod foo { ... }`.",
+        lints: &[],
+    },
+    Rule {
+        id: "unresolved-import",
+        code: Some("E0432"),
+        severity: Severity::Error,
+        summary: "unresolved import",
+        explanation: "A `use` path points into this crate but the final item doesn't exist.\n\n\
+            `use crate::models::Postt;` fails if `crate::models` has no item named `Postt`.\n\
+            Check the spelling (rustpeek suggests close matches) or make sure the item is declared in that module.",
+        lints: &["unused_imports", "unused"],
+    },
+    Rule {
+        id: "unresolved-module",
+        code: Some("E0433"),
+        severity: Severity::Error,
+        summary: "unresolved module in path",
+        explanation: "A `use` path refers to a module that doesn't exist in this crate.\n\n\
+            `use crate::services::Mailer;` fails if there is no `mod services;` reachable from the crate root.\n\
+            Declare the module, or fix the path.",
+        lints: &["unused_imports", "unused"],
+    },
+    Rule {
+        id: "missing-field",
+        code: Some("E0063"),
+        severity: Severity::Error,
+        summary: "missing field in struct initializer",
+        explanation: "A struct literal doesn't set every field of the struct.\n\n\
+            `Config { host }` is an error if `Config` also has a `port` field.\n\
+            Provide the missing fields, or use `..Default::default()` (or another base expression) for the rest.",
+        lints: &[],
+    },
+    Rule {
+        id: "unknown-field",
+        code: Some("E0609"),
+        severity: Severity::Error,
+        summary: "no such field in struct initializer",
+        explanation: "A struct literal sets a field the struct doesn't have.\n\n\
+            `Config { timeout: 5 }` is an error if `Config` has no `timeout` field.\n\
+            Remove the field or fix its name.",
+        lints: &[],
+    },
+    Rule {
+        id: "unknown-variant",
+        code: Some("E0599"),
+        severity: Severity::Error,
+        summary: "no such enum variant",
+        explanation: "A path `Enum::Name` names a variant the enum doesn't define, and there is no associated function by that name either.\n\n\
+            `Color::Yellow` fails if `Color` only has `Red`, `Green` and `Blue`.\n\
+            Fix the variant name or add the variant to the enum.",
+        lints: &[],
+    },
+    Rule {
+        id: "wrong-arg-count",
+        code: Some("E0061"),
+        severity: Severity::Error,
+        summary: "wrong number of function arguments",
+        explanation: "A call to a function defined in this crate passes a different number of arguments than the function declares.\n\n\
+            `add(1, 2, 3)` fails if `fn add(a: i32, b: i32)` takes two.\n\
+            Pass exactly the declared parameters.",
+        lints: &[],
+    },
+    Rule {
+        id: "private-import",
+        code: Some("E0603"),
+        severity: Severity::Error,
+        summary: "item is private",
+        explanation: "A `use` imports an item that isn't visible from the importing module.\n\n\
+            `use crate::inner::helper;` fails if `helper` is declared without `pub` in `inner` and the importer isn't `inner` or one of its children.\n\
+            Make the item `pub` or `pub(crate)`, or stop importing it.",
+        lints: &["unused_imports", "unused"],
+    },
+    Rule {
+        id: "not-imported",
+        code: Some("E0412"),
+        severity: Severity::Suggestion,
+        summary: "name not in scope",
+        explanation: "A type is used without being imported, but an item with that name exists elsewhere in the crate.\n\n\
+            Using `User` in `src/consumer.rs` when it's defined in `crate::models` needs `use crate::models::User;`.\n\
+            rustpeek suggests the import, and `rustpeek fix` inserts it when there's exactly one candidate.",
+        lints: &["dead_code", "unused"],
+    },
+    Rule {
+        id: "unresolved-name",
+        code: Some("E0412"),
+        severity: Severity::Error,
+        summary: "cannot find type in this scope",
+        explanation: "A type name isn't defined anywhere in the crate, and nothing else can bring it into scope.\n\n\
+            rustpeek only reports this when the module has no glob imports that could provide the name, the name isn't\n\
+            in the std prelude or a generic parameter, and it's close to a name that is in scope, e.g. `Uesr` for `User`.\n\
+            Fix the spelling; `rustpeek fix` applies the suggestion when there's a single close name.",
+        lints: &["dead_code", "unused"],
+    },
+    Rule {
+        id: "value-not-imported",
+        code: Some("E0425"),
+        severity: Severity::Suggestion,
+        summary: "value or macro not in scope",
+        explanation: "A function, constant, static or macro is used without being imported, but an item with that name exists elsewhere in the crate.\n\n\
+            Calling `compute_total(order)` when it's defined in `crate::billing` needs `use crate::billing::compute_total;`.\n\
+            Local variables and items declared inside the function are never reported. A `macro_rules!` macro can only be\n\
+            imported if it's `#[macro_export]`ed or re-exported with `use`, and one defined in an enclosing module is already in scope.",
+        lints: &["dead_code", "unused"],
+    },
+    Rule {
+        id: "syntax-error",
+        code: None,
+        severity: Severity::Error,
+        summary: "syntax error",
+        explanation: "A file failed to parse.\n\n\
+            rustpeek stops at syntax errors, since a file that doesn't parse can't be indexed.\n\
+            Fix the reported syntax error and check again.",
+        lints: &[],
+    },
+    Rule {
+        id: "unreadable-file",
+        code: None,
+        severity: Severity::Error,
+        summary: "source file couldn't be read",
+        explanation: "A source file of the crate exists but couldn't be read, e.g. because of permissions or invalid UTF-8.\n\n\
+            The file is skipped; fix the underlying problem and check again.",
+        lints: &[],
+    },
+    Rule {
+        id: "invalid-config",
+        code: None,
+        severity: Severity::Error,
+        summary: "invalid rustpeek configuration",
+        explanation: "`rustpeek.toml` or `[package.metadata.rustpeek]` in Cargo.toml couldn't be read.\n\n\
+            Unknown keys and values of the wrong type are errors. Nothing else is checked until the config is fixed.",
+        lints: &[],
+    },
+    Rule {
+        id: "unused-suppression",
+        code: None,
+        severity: Severity::Suggestion,
+        summary: "unused suppression comment",
+        explanation: "A `// rustpeek: allow(...)` comment names a code that wasn't reported where it applies.\n\n\
+            The code it suppressed was probably fixed. Remove it from the comment; `rustpeek fix` deletes\n\
+            comments that are unused entirely.",
+        lints: &["unused_imports", "unused"],
+    },
+];
+
+/// Look up a rule by its rule ID or error code.
+pub fn lookup(id_or_code: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|r| {
+        r.id.eq_ignore_ascii_case(id_or_code) || r.code.is_some_and(|c| c.eq_ignore_ascii_case(id_or_code))
+    })
+}
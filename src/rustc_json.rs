@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use serde_json::{Value, json};
+
+use crate::lsp::path_to_uri;
+use crate::render::Renderer;
+use crate::rules;
+use crate::types::*;
+
+/// Cargo `--message-format=json` lines for `result`: one `compiler-message`
+/// per diagnostic, then `build-finished`.
+///
+/// The `message` of each line has the shape of a rustc JSON diagnostic, so
+/// tools that parse `cargo check` output can consume rustpeek's unchanged.
+pub fn to_messages(crate_root: &Path, result: &AnalysisResult) -> Vec<Value> {
+    let package = Package::read(crate_root);
+    let mut sources = Sources::default();
+    let mut renderer = Renderer::new(false);
+
+    let mut messages: Vec<Value> = result
+        .diagnostics
+        .iter()
+        .map(|diag| {
+            json!({
+                "reason": "compiler-message",
+                "package_id": package.id,
+                "manifest_path": package.manifest_path,
+                "target": package.target,
+                "message": diagnostic(diag, &mut sources, &mut renderer),
+            })
+        })
+        .collect();
+    messages.push(json!({ "reason": "build-finished", "success": !result.has_errors() }));
+    messages
+}
+
+/// The package fields cargo puts on every message.
+struct Package {
+    id: String,
+    manifest_path: PathBuf,
+    target: Value,
+}
+
+impl Package {
+    fn read(crate_root: &Path) -> Self {
+        let root = crate_root.canonicalize().unwrap_or_else(|_| crate_root.to_path_buf());
+        let manifest_path = root.join("Cargo.toml");
+        let manifest: toml::Table = std::fs::read_to_string(&manifest_path)
+            .ok()
+            .and_then(|content| content.parse().ok())
+            .unwrap_or_default();
+        let package = manifest.get("package").and_then(|p| p.as_table());
+        let field = |key: &str| package.and_then(|p| p.get(key)).and_then(|v| v.as_str()).map(String::from);
+
+        let name = field("name").unwrap_or_default();
+        let version = field("version").unwrap_or_else(|| "0.0.0".to_string());
+        let edition = field("edition").unwrap_or_else(|| "2015".to_string());
+
+        let lib = root.join("src/lib.rs");
+        let (kind, src_path) = if lib.exists() { ("lib", lib) } else { ("bin", root.join("src/main.rs")) };
+
+        Package {
+            id: format!("{}#{name}@{version}", path_to_uri(&root).replacen("file://", "path+file://", 1)),
+            manifest_path,
+            target: json!({
+                "kind": [kind],
+                "crate_types": [kind],
+                "name": name.replace('-', "_"),
+                "src_path": src_path,
+                "edition": edition,
+                "doc": kind == "lib",
+                "doctest": kind == "lib",
+                "test": true,
+            }),
+        }
+    }
+}
+
+fn diagnostic(diag: &Diagnostic, sources: &mut Sources, renderer: &mut Renderer) -> Value {
+    let mut spans = vec![span(&diag.span, true, None, sources)];
+    spans.extend(diag.labels.iter().map(|label| span(&label.span, false, Some(&label.message), sources)));
+
+    let mut children: Vec<Value> = diag.notes.iter().map(|note| child("note", note, Vec::new())).collect();
+
+    // rustc attaches a suggestion to the help message that proposes it
+    let mut suggestion = diag.fix.as_ref().and_then(|fix| suggestion_spans(fix, sources));
+    for help in &diag.help {
+        children.push(child("help", help, suggestion.take().unwrap_or_default()));
+    }
+    if let Some(suggestion) = suggestion {
+        children.push(child("help", "try", suggestion));
+    }
+
+    // Diagnostics without a rustc code report their rule ID, as rustc does for lints
+    let code = match &diag.error_code {
+        Some(code) => code.as_str(),
+        None => diag.rule.as_str(),
+    };
+
+    json!({
+        "$message_type": "diagnostic",
+        "message": diag.message,
+        "code": { "code": code, "explanation": rules::lookup(&diag.rule).map(|r| r.explanation) },
+        "level": level(&diag.severity),
+        "spans": spans,
+        "children": children,
+        "rendered": format!("{}\n", renderer.render(diag)),
+    })
+}
+
+fn level(severity: &Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Suggestion => "warning",
+    }
+}
+
+fn child(level: &str, message: &str, spans: Vec<Value>) -> Value {
+    json!({
+        "message": message,
+        "code": null,
+        "level": level,
+        "spans": spans,
+        "children": [],
+        "rendered": null,
+    })
+}
+
+fn span(span: &Span, is_primary: bool, label: Option<&String>, sources: &mut Sources) -> Value {
+    // Line 0 is the whole file; rustc uses an empty span at its start
+    let (start, end) = if span.start.line == 0 {
+        let start = Position { line: 1, column: 1, byte: 0 };
+        (start, start)
+    } else {
+        (span.start, span.end)
+    };
+    span_value(&span.file, start, end, is_primary, label, None, sources)
+}
+
+/// The spans a fix replaces, with their replacements.
+fn suggestion_spans(fix: &Fix, sources: &mut Sources) -> Option<Vec<Value>> {
+    let applicability = match fix.applicability {
+        Applicability::MachineApplicable => "MachineApplicable",
+        Applicability::MaybeIncorrect => "MaybeIncorrect",
+    };
+    fix.edits
+        .iter()
+        .map(|edit| {
+            let span = edit.span(&sources.get(&edit.file)?);
+            let suggestion = Suggestion { replacement: edit.replacement.clone(), applicability };
+            Some(span_value(&edit.file, span.start, span.end, true, None, Some(suggestion), sources))
+        })
+        .collect()
+}
+
+/// A replacement for a span and how sure rustpeek is of it.
+struct Suggestion {
+    replacement: String,
+    applicability: &'static str,
+}
+
+fn span_value(
+    file: &Path,
+    start: Position,
+    end: Position,
+    is_primary: bool,
+    label: Option<&String>,
+    suggestion: Option<Suggestion>,
+    sources: &mut Sources,
+) -> Value {
+    // The source lines the span covers, with the covered part highlighted
+    let text: Vec<Value> = sources
+        .get(file)
+        .map(|source| {
+            (start.line..=end.line)
+                .filter_map(|line| {
+                    let text = source.lines().nth(line.checked_sub(1)?)?;
+                    let highlight_start = if line == start.line { start.column } else { 1 };
+                    let highlight_end = if line == end.line { end.column } else { text.chars().count() + 1 };
+                    Some(json!({ "text": text, "highlight_start": highlight_start, "highlight_end": highlight_end }))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    json!({
+        "file_name": file,
+        "byte_start": start.byte,
+        "byte_end": end.byte,
+        "line_start": start.line,
+        "line_end": end.line,
+        "column_start": start.column,
+        "column_end": end.column,
+        "is_primary": is_primary,
+        "text": text,
+        "label": label,
+        "suggestion_applicability": suggestion.as_ref().map(|s| s.applicability),
+        "suggested_replacement": suggestion.map(|s| s.replacement),
+        "expansion": null,
+    })
+}
+
+/// Source text of files spans point into, read once per file.
+#[derive(Default)]
+struct Sources {
+    files: HashMap<PathBuf, Option<Rc<str>>>,
+}
+
+impl Sources {
+    fn get(&mut self, file: &Path) -> Option<Rc<str>> {
+        self.files
+            .entry(file.to_path_buf())
+            .or_insert_with(|| crate::read_file(file).ok().map(Rc::from))
+            .clone()
+    }
+}
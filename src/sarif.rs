@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Value, json};
+
+use crate::baseline;
+use crate::lsp::{path_to_uri, percent_encode_path};
+use crate::rules::{self, RULES};
+use crate::types::*;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Base URI id that artifact locations are relative to: the crate root.
+const SRCROOT: &str = "SRCROOT";
+
+/// Build a SARIF 2.1.0 log with one run for `result`.
+///
+/// Every rule rustpeek has is listed in the driver, so dashboards can show
+/// descriptions for rules with no results too. Paths are relative to the
+/// crate root, which is given as the `SRCROOT` base URI.
+pub fn to_sarif(crate_root: &Path, result: &AnalysisResult) -> Value {
+    let root = crate_root.canonicalize().unwrap_or_else(|_| crate_root.to_path_buf());
+    let mut sources = Sources::default();
+    let fingerprints = baseline::fingerprints(crate_root, result);
+
+    let results: Vec<Value> = result
+        .diagnostics
+        .iter()
+        .zip(fingerprints)
+        .map(|(diag, fingerprint)| sarif_result(crate_root, diag, &fingerprint, &mut sources))
+        .collect();
+
+    json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "rustpeek",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": RULES.iter().map(sarif_rule).collect::<Vec<_>>(),
+                },
+            },
+            "originalUriBaseIds": {
+                SRCROOT: { "uri": format!("{}/", path_to_uri(&root).trim_end_matches('/')) },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    })
+}
+
+fn sarif_rule(rule: &rules::Rule) -> Value {
+    let mut value = json!({
+        "id": rule.id,
+        "shortDescription": { "text": rule.summary },
+        // The first paragraph of the explanation says what the rule reports
+        "fullDescription": { "text": rule.explanation.split("\n\n").next().unwrap_or(rule.explanation) },
+        "help": { "text": rule.explanation },
+        "defaultConfiguration": { "level": level(&rule.severity) },
+    });
+    if let Some(code) = rule.code {
+        value["properties"] = json!({ "rustcCode": code });
+    }
+    value
+}
+
+fn level(severity: &Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Suggestion => "warning",
+    }
+}
+
+fn sarif_result(crate_root: &Path, diag: &Diagnostic, fingerprint: &str, sources: &mut Sources) -> Value {
+    // SARIF messages are plain text; fold notes and help into it
+    let mut message = diag.message.clone();
+    for note in &diag.notes {
+        message.push_str(&format!("\nnote: {note}"));
+    }
+    for help in &diag.help {
+        message.push_str(&format!("\nhelp: {help}"));
+    }
+
+    let mut value = json!({
+        "ruleId": diag.rule,
+        "level": level(&diag.severity),
+        "message": { "text": message },
+        "locations": [{ "physicalLocation": physical_location(crate_root, &diag.span) }],
+        "partialFingerprints": { "rustpeek/v1": fingerprint },
+    });
+    if let Some(index) = RULES.iter().position(|r| r.id == diag.rule) {
+        value["ruleIndex"] = json!(index);
+    }
+    if !diag.labels.is_empty() {
+        value["relatedLocations"] = diag
+            .labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| {
+                json!({
+                    "id": i + 1,
+                    "physicalLocation": physical_location(crate_root, &label.span),
+                    "message": { "text": label.message },
+                })
+            })
+            .collect();
+    }
+    if let Some(fix) = &diag.fix
+        && let Some(fix) = sarif_fix(crate_root, fix, sources)
+    {
+        value["fixes"] = json!([fix]);
+    }
+    value
+}
+
+fn artifact_location(crate_root: &Path, file: &Path) -> Value {
+    json!({
+        "uri": percent_encode_path(&baseline::relative_path(crate_root, file)),
+        "uriBaseId": SRCROOT,
+    })
+}
+
+fn physical_location(crate_root: &Path, span: &Span) -> Value {
+    let mut location = json!({ "artifactLocation": artifact_location(crate_root, &span.file) });
+    // Line 0 means the whole file, which SARIF expresses by leaving out the region
+    if span.start.line > 0 {
+        location["region"] = json!({
+            "startLine": span.start.line,
+            "startColumn": span.start.column,
+            "endLine": span.end.line,
+            "endColumn": span.end.column,
+            "byteOffset": span.start.byte,
+            "byteLength": span.end.byte.saturating_sub(span.start.byte),
+        });
+    }
+    location
+}
+
+/// A SARIF fix with one artifact change per file the fix edits, or None if a
+/// file can't be read to locate its edits.
+fn sarif_fix(crate_root: &Path, fix: &Fix, sources: &mut Sources) -> Option<Value> {
+    let mut changes: Vec<(&Path, Vec<Value>)> = Vec::new();
+    for edit in &fix.edits {
+        let span = edit.span(sources.text(&edit.file)?);
+        let replacement = json!({
+            "deletedRegion": {
+                "startLine": span.start.line,
+                "startColumn": span.start.column,
+                "endLine": span.end.line,
+                "endColumn": span.end.column,
+                "byteOffset": span.start.byte,
+                "byteLength": span.end.byte - span.start.byte,
+            },
+            "insertedContent": { "text": edit.replacement },
+        });
+        match changes.iter_mut().find(|(file, _)| *file == edit.file) {
+            Some((_, replacements)) => replacements.push(replacement),
+            None => changes.push((&edit.file, vec![replacement])),
+        }
+    }
+
+    Some(json!({
+        "description": { "text": fix.message },
+        "artifactChanges": changes
+            .into_iter()
+            .map(|(file, replacements)| json!({
+                "artifactLocation": artifact_location(crate_root, file),
+                "replacements": replacements,
+            }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+/// Source text of files that fixes touch, read once per file.
+#[derive(Default)]
+struct Sources {
+    files: HashMap<PathBuf, Option<String>>,
+}
+
+impl Sources {
+    fn text(&mut self, file: &Path) -> Option<&str> {
+        self.files
+            .entry(file.to_path_buf())
+            .or_insert_with(|| crate::read_file(file).ok())
+            .as_deref()
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, Config};
+use crate::discovery::{self, CrateFiles};
+use crate::types::*;
+
+/// A long-lived analysis of one crate.
+///
+/// Keeps the discovered file list and symbol table between runs, so
+/// re-checking after an edit only re-indexes the files that changed.
+pub struct Session {
+    crate_root: PathBuf,
+    crate_files: CrateFiles,
+    symbols: SymbolTable,
+    /// The crate's config with `overrides` applied
+    config: Config,
+    /// Settings layered over the crate's config file (e.g. CLI flags)
+    overrides: Config,
+    /// Why the crate's config couldn't be loaded, if it couldn't
+    config_error: Option<String>,
+}
+
+impl Session {
+    /// Load the crate's config, then discover and index the whole crate.
+    pub fn new(crate_root: &Path, overrides: Config) -> Self {
+        let (mut config, config_error) = match config::load(crate_root) {
+            Ok(config) => (config, None),
+            Err(e) => (Config::default(), Some(e)),
+        };
+        config.merge(&overrides);
+
+        let crate_files = discovery::discover_crate(crate_root);
+        let symbols = crate::build_symbol_table(&crate_files, &HashMap::new(), &config);
+        Session {
+            crate_root: crate_root.to_path_buf(),
+            crate_files,
+            symbols,
+            config,
+            overrides,
+            config_error,
+        }
+    }
+
+    pub fn crate_root(&self) -> &Path {
+        &self.crate_root
+    }
+
+    /// Throw away all cached state, reload the config and rediscover the
+    /// crate from scratch. Needed when Cargo.toml or rustpeek.toml changes,
+    /// or files are added or removed.
+    pub fn rediscover(&mut self) {
+        let overrides = std::mem::take(&mut self.overrides);
+        *self = Session::new(&self.crate_root, overrides);
+    }
+
+    /// Re-index files that were modified on disk.
+    ///
+    /// Files that are new to the crate or no longer exist trigger a full
+    /// rediscovery, since the module tree itself may have changed.
+    pub fn files_changed(&mut self, paths: &[PathBuf]) {
+        let mut to_reindex = Vec::new();
+        for path in paths {
+            let known = self.crate_files.files.iter().find(|(_, p)| *p == path);
+            match known {
+                Some((module_path, file_path)) if file_path.exists() => {
+                    to_reindex.push((module_path.clone(), file_path.clone()));
+                }
+                _ => {
+                    self.rediscover();
+                    return;
+                }
+            }
+        }
+
+        for (module_path, file_path) in to_reindex {
+            match crate::index_module(&module_path, &file_path, &HashMap::new(), &self.config) {
+                Some(info) => {
+                    self.symbols.modules.insert(module_path, info);
+                }
+                None => {
+                    self.symbols.modules.remove(&module_path);
+                }
+            }
+        }
+    }
+
+    /// Run analysis against the cached symbol table.
+    pub fn analyze(&self, changed_files: Option<&[PathBuf]>) -> AnalysisResult {
+        if let Some(message) = &self.config_error {
+            return crate::config_error(&self.crate_root, message.clone());
+        }
+        crate::analyze_discovered(
+            &self.crate_root,
+            &self.crate_files,
+            changed_files,
+            &HashMap::new(),
+            &self.config,
+            Some(&self.symbols),
+        )
+    }
+}
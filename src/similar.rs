@@ -0,0 +1,280 @@
+//! Similar-name matching for "did you mean?" suggestions.
+
+/// Most candidates a hint lists.
+pub const MAX_SHOWN: usize = 3;
+
+/// Candidates with names similar to a name, closest first.
+pub struct Suggestions<T> {
+    /// Each candidate with its score (lower is closer)
+    ranked: Vec<(T, u32)>,
+}
+
+impl<T> Suggestions<T> {
+    /// Rank the candidates whose names, given by `name_of`, are similar to `name`.
+    /// Ties are ordered by name, so the ranking doesn't depend on the input order.
+    pub fn new(name: &str, candidates: impl IntoIterator<Item = T>, name_of: impl Fn(&T) -> &str) -> Self {
+        let mut ranked: Vec<(T, u32)> = candidates
+            .into_iter()
+            .filter_map(|candidate| score(name, name_of(&candidate)).map(|score| (candidate, score)))
+            .collect();
+        ranked.sort_by(|(a, a_score), (b, b_score)| a_score.cmp(b_score).then_with(|| name_of(a).cmp(name_of(b))));
+        Suggestions { ranked }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranked.is_empty()
+    }
+
+    /// The closest candidate, if it's closer than every other one; only then
+    /// is it worth a fix.
+    pub fn best(&self) -> Option<&T> {
+        match self.ranked.as_slice() {
+            [(best, _)] => Some(best),
+            [(best, best_score), (_, next_score), ..] if best_score < next_score => Some(best),
+            _ => None,
+        }
+    }
+
+    /// The closest candidates, at most [`MAX_SHOWN`].
+    pub fn shown(&self) -> impl Iterator<Item = &T> {
+        self.ranked.iter().take(MAX_SHOWN).map(|(candidate, _)| candidate)
+    }
+}
+
+/// `did you mean `a`?`, `did you mean `a` or `b`?` or `did you mean `a`, `b` or `c`?`
+pub fn did_you_mean<'n>(names: impl IntoIterator<Item = &'n str>) -> String {
+    let names: Vec<String> = names.into_iter().map(|name| format!("`{name}`")).collect();
+    let list = match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {last}", rest.join(", ")),
+        _ => names.concat(),
+    };
+    format!("did you mean {list}?")
+}
+
+/// How close `candidate` is to `name`, lower being closer, or None if it's
+/// too different to suggest. From closest: the same name in another case,
+/// the singular or plural form, then a few edits away (a transposition
+/// counts as one) or the same words with some abbreviated (`UserRepo` for
+/// `UserRepository`).
+pub fn score(name: &str, candidate: &str) -> Option<u32> {
+    if name == candidate {
+        return None; // exact match isn't "similar", it's the same
+    }
+
+    let name_lower = name.to_lowercase();
+    let candidate_lower = candidate.to_lowercase();
+    if name_lower == candidate_lower {
+        return Some(0);
+    }
+    if singular(&name_lower) == singular(&candidate_lower) {
+        return Some(1);
+    }
+
+    // Allow distance proportional to length:
+    //   len 1-4: distance <= 1
+    //   len 5-8: distance <= 2
+    //   len 9+:  distance <= 3
+    let max_len = name.chars().count().max(candidate.chars().count());
+    let threshold = match max_len {
+        0..=4 => 1,
+        5..=8 => 2,
+        _ => 3,
+    };
+    let by_edits = Some(damerau_levenshtein(&name_lower, &candidate_lower))
+        .filter(|distance| *distance <= threshold)
+        .map(|distance| 1 + distance as u32);
+    let by_words = same_words(name, candidate).then_some(3);
+
+    by_edits.into_iter().chain(by_words).min()
+}
+
+/// The words of a snake_case or CamelCase name, lowercased.
+fn words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        // A new word starts at `aB`, `1B`, and at the `B` of `ABc`
+        let starts_word = c.is_uppercase()
+            && i > 0
+            && (!chars[i - 1].is_uppercase() || chars.get(i + 1).is_some_and(|next| next.is_lowercase()));
+        if starts_word && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// Whether the names have the same words, allowing singular for plural and
+/// abbreviations of at least three letters, as long as some word is whole.
+fn same_words(a: &str, b: &str) -> bool {
+    let (a, b) = (words(a), words(b));
+    let whole = |a: &String, b: &String| singular(a) == singular(b);
+    let abbreviated = |a: &String, b: &String| {
+        let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+        shorter.len() >= 3 && longer.starts_with(shorter.as_str())
+    };
+    a.len() == b.len()
+        && a.iter().zip(&b).all(|(a, b)| whole(a, b) || abbreviated(a, b))
+        && a.iter().zip(&b).any(|(a, b)| whole(a, b))
+}
+
+/// A lowercase word or name with a plural ending made singular.
+fn singular(word: &str) -> String {
+    if word.len() > 3 && word.ends_with("ies") {
+        return format!("{}y", &word[..word.len() - 3]);
+    }
+    for ending in ["sses", "xes", "ches", "shes"] {
+        if word.ends_with(ending) {
+            return word[..word.len() - 2].to_string();
+        }
+    }
+    if word.len() > 1 && word.ends_with('s') && !word.ends_with("ss") {
+        return word[..word.len() - 1].to_string();
+    }
+    word.to_string()
+}
+
+/// Edit distance counting insertions, deletions, substitutions and
+/// transpositions of adjacent characters (optimal string alignment).
+fn damerau_levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    if a.is_empty() { return b.len(); }
+    if b.is_empty() { return a.len(); }
+
+    // Rows i-2, i-1 and i of the distance matrix
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        curr[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr[j] = (prev[j - 1] + cost)
+                .min(prev[j] + 1)
+                .min(curr[j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                curr[j] = curr[j].min(before[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut before, &mut prev);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_ranks_case_then_plural_then_edits() {
+        assert_eq!(score("User", "User"), None);
+        assert_eq!(score("user", "User"), Some(0));
+        assert_eq!(score("Users", "User"), Some(1));
+        assert_eq!(score("Entries", "Entry"), Some(1));
+        assert_eq!(score("Uesr", "User"), Some(2), "a transposition is one edit");
+        assert_eq!(score("Usr", "User"), Some(2));
+        assert_eq!(score("Postt", "Post"), Some(2));
+    }
+
+    #[test]
+    fn score_allows_more_edits_for_longer_names() {
+        assert_eq!(score("Abc", "Xyz"), None);
+        assert_eq!(score("Cfg", "Cog"), Some(2));
+        assert_eq!(score("Cfgx", "Cogy"), None, "two edits is too many for four letters");
+        assert_eq!(score("Settngs", "Setting"), Some(3));
+        assert_eq!(score("Configuration", "Konfigurasion"), Some(3));
+        assert_eq!(score("Configuration", "Kanfiguraziom"), None);
+    }
+
+    #[test]
+    fn score_matches_abbreviated_words() {
+        assert_eq!(score("UserRepo", "UserRepository"), Some(3));
+        assert_eq!(score("user_repo", "UserRepository"), Some(3));
+        assert_eq!(score("UsrRepo", "UserRepository"), None, "abbreviations need three letters");
+        assert_eq!(score("Repo", "Repository"), None, "some word must be whole");
+    }
+
+    #[test]
+    fn words_split_snake_and_camel_case() {
+        assert_eq!(words("user_repository"), ["user", "repository"]);
+        assert_eq!(words("UserRepository"), ["user", "repository"]);
+        assert_eq!(words("HTTPServer"), ["http", "server"]);
+        assert_eq!(words("Base64Codec"), ["base64", "codec"]);
+    }
+
+    #[test]
+    fn same_words_allows_plurals_and_prefixes() {
+        assert!(same_words("UsersRepo", "UserRepository"));
+        assert!(same_words("ConfigOpt", "ConfigOptions"));
+        assert!(!same_words("ConfigOpt", "ConfigOptionsBuilder"), "word counts differ");
+        assert!(!same_words("ConfigOpts", "ConfigOptions"), "an abbreviation is a prefix");
+        assert!(!same_words("UserRepo", "AdminRepository"));
+    }
+
+    #[test]
+    fn singular_strips_plural_endings() {
+        assert_eq!(singular("users"), "user");
+        assert_eq!(singular("entries"), "entry");
+        assert_eq!(singular("boxes"), "box");
+        assert_eq!(singular("matches"), "match");
+        assert_eq!(singular("classes"), "class");
+        assert_eq!(singular("class"), "class");
+        assert_eq!(singular("ies"), "ie");
+        assert_eq!(singular("s"), "s");
+    }
+
+    #[test]
+    fn damerau_levenshtein_counts_transpositions_once() {
+        assert_eq!(damerau_levenshtein("user", "user"), 0);
+        assert_eq!(damerau_levenshtein("uesr", "user"), 1);
+        assert_eq!(damerau_levenshtein("", "abc"), 3);
+        assert_eq!(damerau_levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn best_needs_a_strict_margin() {
+        let only = Suggestions::new("Usr", ["User"], |name| name);
+        assert_eq!(only.best(), Some(&"User"));
+
+        let clear = Suggestions::new("user", ["Users", "User"], |name| name);
+        assert_eq!(clear.best(), Some(&"User"));
+
+        let tied = Suggestions::new("Tset", ["Test", "Set"], |name| name);
+        assert_eq!(tied.best(), None);
+        assert_eq!(tied.shown().collect::<Vec<_>>(), [&"Set", &"Test"], "ties are ordered by name");
+
+        let none = Suggestions::new("Widget", ["User"], |name| name);
+        assert!(none.is_empty());
+        assert_eq!(none.best(), None);
+    }
+
+    #[test]
+    fn shown_lists_at_most_max_shown() {
+        let many = Suggestions::new("Item", ["Items", "Iter", "Itm", "Ite", "item"], |name| name);
+        assert_eq!(many.shown().count(), MAX_SHOWN);
+        assert_eq!(many.shown().next(), Some(&"item"));
+    }
+
+    #[test]
+    fn did_you_mean_lists_names() {
+        assert_eq!(did_you_mean(["User"]), "did you mean `User`?");
+        assert_eq!(did_you_mean(["User", "Users"]), "did you mean `User` or `Users`?");
+        assert_eq!(did_you_mean(["A", "B", "C"]), "did you mean `A`, `B` or `C`?");
+    }
+}
@@ -0,0 +1,351 @@
+use std::ops::{Range, RangeInclusive};
+use std::path::Path;
+
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use crate::config::Config;
+use crate::types::*;
+
+/// A `// rustpeek: allow(...)` or `// rustpeek: allow-file(...)` comment.
+#[derive(Debug, Clone)]
+pub struct Suppression {
+    /// Line the comment is on (1-indexed)
+    pub line: usize,
+    /// Line whose diagnostics it suppresses; None for `allow-file`
+    pub target_line: Option<usize>,
+    /// Rule IDs and codes it suppresses
+    pub codes: Vec<String>,
+    /// Whether the comment is alone on its line (so it can be removed whole)
+    pub own_line: bool,
+    /// Byte range of the comment in the source
+    pub comment: std::ops::Range<usize>,
+}
+
+/// Find every suppression comment in a source file.
+///
+/// `allow(...)` alone on a line applies to the next line that isn't a comment;
+/// at the end of a line of code it applies to that line. `allow-file(...)`
+/// applies to the whole file wherever it appears.
+pub fn parse(source: &str) -> Vec<Suppression> {
+    let lines: Vec<&str> = source.lines().collect();
+    let line_starts: Vec<usize> = source
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some(start)
+        })
+        .collect();
+    let mut suppressions = Vec::new();
+
+    for comment in line_comments(source) {
+        let i = line_starts.partition_point(|&start| start <= comment.start) - 1;
+        let line = lines[i];
+        let comment_start = comment.start - line_starts[i];
+        let comment = line[comment_start + 2..].trim_start_matches('/').trim();
+        let Some(directive) = comment.strip_prefix("rustpeek:") else {
+            continue;
+        };
+        let directive = directive.trim();
+
+        let (file_level, args) = if let Some(args) = directive.strip_prefix("allow-file(") {
+            (true, args)
+        } else if let Some(args) = directive.strip_prefix("allow(") {
+            (false, args)
+        } else {
+            continue;
+        };
+        let Some(args) = args.split(')').next() else {
+            continue;
+        };
+        let codes: Vec<String> = args
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(String::from)
+            .collect();
+        if codes.is_empty() {
+            continue;
+        }
+
+        let own_line = line[..comment_start].trim().is_empty();
+        let target_line = if file_level {
+            None
+        } else if own_line {
+            // Skip over stacked comments to the line they annotate
+            let next = lines[i + 1..]
+                .iter()
+                .position(|l| !l.trim_start().starts_with("//"))
+                .map(|offset| i + 1 + offset + 1);
+            Some(next.unwrap_or(i + 2))
+        } else {
+            Some(i + 1)
+        };
+
+        suppressions.push(Suppression {
+            line: i + 1,
+            target_line,
+            codes,
+            own_line,
+            comment: line_starts[i] + comment_start..line_starts[i] + line.len(),
+        });
+    }
+
+    suppressions
+}
+
+/// Byte ranges of the `//` comments in `source`, each up to the end of its
+/// line. Slashes in string and character literals or block comments don't
+/// start one.
+fn line_comments(source: &str) -> Vec<Range<usize>> {
+    let bytes = source.as_bytes();
+    let is_ident = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'_';
+    // Just past `closing` at or after `from`, or the end of the source
+    let past = |from: usize, closing: &str| {
+        source.get(from..).and_then(|rest| rest.find(closing)).map_or(source.len(), |at| from + at + closing.len())
+    };
+    let starts_token = |at: usize| at == 0 || !is_ident(bytes[at - 1]);
+    let mut comments = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        i = match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                let end = source[i..].find('\n').map_or(source.len(), |at| i + at);
+                comments.push(i..end);
+                end
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                // Block comments nest
+                let mut depth = 0;
+                let mut j = i;
+                while j < bytes.len() {
+                    if bytes[j..].starts_with(b"/*") {
+                        depth += 1;
+                        j += 2;
+                    } else if bytes[j..].starts_with(b"*/") {
+                        depth -= 1;
+                        j += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        j += 1;
+                    }
+                }
+                j
+            }
+            // `r"..."` and `r#"..."#`, also after `b` or `c`
+            b'r' if starts_token(i) || (matches!(bytes[i - 1], b'b' | b'c') && starts_token(i - 1)) => {
+                let hashes = bytes[i + 1..].iter().take_while(|&&b| b == b'#').count();
+                if bytes.get(i + 1 + hashes) == Some(&b'"') {
+                    past(i + 2 + hashes, &format!("\"{}", "#".repeat(hashes)))
+                } else {
+                    i + 1
+                }
+            }
+            b'"' => {
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j] != b'"' {
+                    j += if bytes[j] == b'\\' { 2 } else { 1 };
+                }
+                j + 1
+            }
+            // A character literal, or else a lifetime or label, which has no closing quote
+            b'\'' if bytes.get(i + 1) == Some(&b'\\') => past(i + 3, "'"),
+            b'\'' => {
+                let width = source[i + 1..].chars().next().map_or(0, char::len_utf8);
+                if bytes.get(i + 1 + width) == Some(&b'\'') { i + 2 + width } else { i + 1 }
+            }
+            _ => i + 1,
+        };
+    }
+    comments
+}
+
+/// Remove diagnostics in `file` that are covered by a suppression comment in
+/// `source`, and add a diagnostic for every suppressed code that matched nothing.
+///
+/// Codes the config disables are never reported as unused, since whether they
+/// would match can't be known.
+pub fn apply(diagnostics: &mut Vec<Diagnostic>, file: &Path, source: &str, config: &Config) {
+    let suppressions = parse(source);
+    if suppressions.is_empty() {
+        return;
+    }
+
+    // (suppression index, code index) pairs that matched something
+    let mut used = vec![Vec::new(); suppressions.len()];
+
+    diagnostics.retain(|diag| {
+        if diag.file() != file {
+            return true;
+        }
+        let mut suppressed = false;
+        for (i, suppression) in suppressions.iter().enumerate() {
+            if suppression.target_line.is_some_and(|line| line != diag.line()) {
+                continue;
+            }
+            if let Some(j) = suppression.codes.iter().position(|name| diag.is_named(name)) {
+                used[i].push(j);
+                suppressed = true;
+            }
+        }
+        !suppressed
+    });
+
+    for (suppression, used) in suppressions.iter().zip(&used) {
+        let unused: Vec<&String> = suppression
+            .codes
+            .iter()
+            .enumerate()
+            .filter(|(j, code)| !used.contains(j) && config.is_enabled(code))
+            .map(|(_, code)| code)
+            .collect();
+        if unused.is_empty() {
+            continue;
+        }
+
+        // Only offer to delete the comment when nothing on it is still needed
+        let fix = (suppression.own_line && used.is_empty()).then(|| {
+            let start = source[..suppression.comment.start].rfind('\n').map_or(0, |i| i + 1);
+            let end = source[suppression.comment.end..]
+                .find('\n')
+                .map_or(source.len(), |i| suppression.comment.end + i + 1);
+            Fix::new(
+                "remove the suppression comment",
+                Applicability::MachineApplicable,
+                vec![TextEdit {
+                    file: file.to_path_buf(),
+                    byte_range: start..end,
+                    replacement: String::new(),
+                }],
+            )
+        });
+        let codes: Vec<String> = unused.iter().map(|c| format!("`{c}`")).collect();
+        let span = Span::from_bytes(file, source, suppression.comment.clone());
+        diagnostics.push(
+            Diagnostic::suggestion(
+                "unused-suppression",
+                span,
+                format!("unused rustpeek suppression for {}", codes.join(", ")),
+            )
+            .with_note("no diagnostic with this code was reported here".to_string())
+            .with_help("remove it from the comment".to_string())
+            .with_fix(fix),
+        );
+    }
+}
+
+/// The lints `#[allow(...)]` attributes among `attrs` allow, e.g. `dead_code`
+/// or `clippy::all`. Inner `#![allow(...)]` attributes count too.
+pub(crate) fn allowed_lints(attrs: &[syn::Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("allow"))
+        .filter_map(|attr| attr.parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated).ok())
+        .flatten()
+        .filter_map(|meta| match meta {
+            // Skips `reason = "..."`
+            syn::Meta::Path(path) => {
+                Some(path.segments.iter().map(|s| s.ident.to_string()).collect::<Vec<_>>().join("::"))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Remove diagnostics in `file` that a rustc `#[allow(...)]` attribute covers:
+/// one on an item around the diagnostic, a `#![allow(...)]` in the file or in
+/// an ancestor module's file (up to the crate root), or one on the `mod`
+/// declaration of the module or an ancestor. An attribute silences the rules
+/// that mirror the lints it allows (see [`crate::rules::Rule::lints`]).
+pub fn apply_allows(
+    diagnostics: &mut Vec<Diagnostic>,
+    file: &Path,
+    ast: &syn::File,
+    module_path: &ModulePath,
+    symbols: &SymbolTable,
+) {
+    let mut module_lints = allowed_lints(&ast.attrs);
+    for depth in 1..module_path.0.len() {
+        let Some(ancestor) = symbols.modules.get(&ModulePath(module_path.0[..depth].to_vec())) else {
+            continue;
+        };
+        let child = &module_path.0[depth];
+        module_lints.extend(ancestor.allowed_lints.iter().cloned());
+        module_lints.extend(
+            ancestor
+                .child_allowed_lints
+                .iter()
+                .filter(|(name, _)| name == child)
+                .flat_map(|(_, lints)| lints.iter().cloned()),
+        );
+    }
+    let mut items = ItemAllows::default();
+    items.visit_file(ast);
+    if module_lints.is_empty() && items.allows.is_empty() {
+        return;
+    }
+
+    diagnostics.retain(|diag| {
+        if diag.file() != file {
+            return true;
+        }
+        let Some(rule) = crate::rules::lookup(&diag.rule) else {
+            return true;
+        };
+        let silences = |lints: &[String]| lints.iter().any(|lint| rule.lints.contains(&lint.as_str()));
+        let in_item = diag.line() > 0
+            && items.allows.iter().any(|(lines, lints)| lines.contains(&diag.line()) && silences(lints));
+        !(silences(&module_lints) || in_item)
+    });
+}
+
+/// The lints `#[allow(...)]` attributes allow on items of a file, with the
+/// lines each item spans.
+#[derive(Default)]
+struct ItemAllows {
+    allows: Vec<(RangeInclusive<usize>, Vec<String>)>,
+}
+
+impl ItemAllows {
+    fn add(&mut self, attrs: &[syn::Attribute], span: proc_macro2::Span) {
+        let lints = allowed_lints(attrs);
+        if !lints.is_empty() {
+            self.allows.push((span.start().line..=span.end().line, lints));
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ItemAllows {
+    fn visit_item(&mut self, item: &'ast syn::Item) {
+        self.add(crate::cfg::item_attrs(item), item.span());
+        syn::visit::visit_item(self, item);
+    }
+
+    fn visit_impl_item(&mut self, item: &'ast syn::ImplItem) {
+        let attrs: &[syn::Attribute] = match item {
+            syn::ImplItem::Const(i) => &i.attrs,
+            syn::ImplItem::Fn(i) => &i.attrs,
+            syn::ImplItem::Type(i) => &i.attrs,
+            syn::ImplItem::Macro(i) => &i.attrs,
+            _ => &[],
+        };
+        self.add(attrs, item.span());
+        syn::visit::visit_impl_item(self, item);
+    }
+
+    fn visit_trait_item(&mut self, item: &'ast syn::TraitItem) {
+        let attrs: &[syn::Attribute] = match item {
+            syn::TraitItem::Const(i) => &i.attrs,
+            syn::TraitItem::Fn(i) => &i.attrs,
+            syn::TraitItem::Type(i) => &i.attrs,
+            syn::TraitItem::Macro(i) => &i.attrs,
+            _ => &[],
+        };
+        self.add(attrs, item.span());
+        syn::visit::visit_trait_item(self, item);
+    }
+}
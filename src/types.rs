@@ -1,7 +1,7 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// A module path like `crate::parser::utils`
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -36,6 +36,12 @@ impl ModulePath {
     }
 }
 
+impl Serialize for ModulePath {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.display())
+    }
+}
+
 impl std::fmt::Display for ModulePath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.display())
@@ -43,7 +49,8 @@ impl std::fmt::Display for ModulePath {
 }
 
 /// Visibility of an item
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Vis {
     Public,
     PubCrate,
@@ -92,7 +99,8 @@ impl Vis {
 }
 
 /// Kind of item in the symbol table
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ItemKind {
     Struct,
     Enum,
@@ -101,19 +109,20 @@ pub enum ItemKind {
     TypeAlias,
     Const,
     Static,
+    Union,
     Macro,
     Module,
 }
 
 /// A field in a struct
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct FieldInfo {
     pub name: String,
     pub vis: Vis,
 }
 
 /// An enum variant
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct VariantInfo {
     pub name: String,
     pub field_count: usize,
@@ -122,7 +131,7 @@ pub struct VariantInfo {
 }
 
 /// An item in the symbol table
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ItemInfo {
     pub name: String,
     pub kind: ItemKind,
@@ -132,12 +141,14 @@ pub struct ItemInfo {
     pub fields: Vec<FieldInfo>,
     /// Variants for enums
     pub variants: Vec<VariantInfo>,
-    /// Parameter count for functions (None if not a function)
+    /// Parameter count for functions and tuple struct constructors (None otherwise)
     pub param_count: Option<usize>,
+    /// The item's name in its defining file
+    pub span: Span,
 }
 
 /// A use statement
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct UseInfo {
     /// The full path being imported (e.g., `crate::parser::Parser`)
     pub path: Vec<String>,
@@ -150,7 +161,7 @@ pub struct UseInfo {
 }
 
 /// An impl block
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ImplInfo {
     /// The type name this impl is for (just the ident, not resolved)
     pub type_name: String,
@@ -159,7 +170,7 @@ pub struct ImplInfo {
 }
 
 /// A method in an impl block
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct MethodInfo {
     pub name: String,
     pub vis: Vis,
@@ -169,7 +180,7 @@ pub struct MethodInfo {
 }
 
 /// All indexed information for a single module
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct ModuleInfo {
     pub items: Vec<ItemInfo>,
     pub uses: Vec<UseInfo>,
@@ -177,6 +188,12 @@ pub struct ModuleInfo {
     pub file_path: PathBuf,
     /// mod declarations in this module (child module names)
     pub child_modules: Vec<String>,
+    /// Child modules declared with `#[macro_use]`, whose `macro_rules!` macros stay in scope after them
+    pub macro_use_modules: Vec<String>,
+    /// Lints allowed in the whole module by `#![allow(...)]` at the top of its file
+    pub allowed_lints: Vec<String>,
+    /// Lints allowed by `#[allow(...)]` on child module declarations, by child name
+    pub child_allowed_lints: Vec<(String, Vec<String>)>,
 }
 
 /// The full crate symbol table
@@ -227,93 +244,259 @@ impl SymbolTable {
 }
 
 /// Severity of a diagnostic
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Severity {
     Error,
     Suggestion,
 }
 
-/// A proposed auto-fix for a diagnostic
+/// How confident rustpeek is that a fix is what the user wants, as in rustc
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Applicability {
+    /// The fix is definitely what's intended and can be applied without review
+    MachineApplicable,
+    /// The fix is likely what's intended, but may be wrong (e.g. a typo guess)
+    MaybeIncorrect,
+}
+
+/// Replace a byte range of a file. An empty range inserts, an empty
+/// replacement deletes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TextEdit {
+    pub file: PathBuf,
+    pub byte_range: std::ops::Range<usize>,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    /// The span the edit replaces, given the current text of its file.
+    pub fn span(&self, source: &str) -> Span {
+        Span::from_bytes(&self.file, source, self.byte_range.clone())
+    }
+}
+
+/// A proposed auto-fix for a diagnostic: edits that are applied together
 #[derive(Debug, Clone, Serialize)]
-#[serde(tag = "kind")]
-pub enum Fix {
-    /// Insert a line at the given position
-    InsertLine {
-        file: PathBuf,
-        /// Line number to insert BEFORE (1-indexed). 0 means append to end.
-        line: usize,
-        content: String,
-    },
-    /// Replace text on a specific line
-    ReplaceLine {
-        file: PathBuf,
-        line: usize,
-        old_text: String,
-        new_text: String,
-    },
-    /// Remove a line entirely
-    RemoveLine {
-        file: PathBuf,
-        line: usize,
-    },
+pub struct Fix {
+    /// What the fix does, e.g. "replace `Postt` with `Post`"
+    pub message: String,
+    pub applicability: Applicability,
+    /// Non-overlapping edits, in any order
+    pub edits: Vec<TextEdit>,
+    /// Name the fix brings into scope in the file it edits, e.g. by adding an
+    /// import, prefixed with the inline modules it's bound in (`tests::User`).
+    /// Two fixes binding the same name in one file conflict.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binds: Option<String>,
+}
+
+impl Fix {
+    pub fn new(message: impl Into<String>, applicability: Applicability, edits: Vec<TextEdit>) -> Self {
+        Fix {
+            message: message.into(),
+            applicability,
+            edits,
+            binds: None,
+        }
+    }
+
+    pub fn binding(mut self, name: impl Into<String>) -> Self {
+        self.binds = Some(name.into());
+        self
+    }
+
+    /// The file the fix edits (the file of its first edit)
+    pub fn file(&self) -> &Path {
+        self.edits.first().map_or(Path::new(""), |edit| &edit.file)
+    }
 }
 
 impl std::fmt::Display for Fix {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Fix::InsertLine { file, line, content } => {
-                write!(f, "insert at {}:{}: {}", file.display(), line, content.trim())
-            }
-            Fix::ReplaceLine { file, line, old_text, new_text } => {
-                write!(f, "replace at {}:{}: `{}` → `{}`", file.display(), line, old_text.trim(), new_text.trim())
-            }
-            Fix::RemoveLine { file, line } => {
-                write!(f, "remove {}:{}", file.display(), line)
-            }
+        write!(f, "{}: {}", self.file().display(), self.message)
+    }
+}
+
+/// A location in a source file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Position {
+    /// 1-indexed line (0 when the diagnostic is about the file as a whole)
+    pub line: usize,
+    /// 1-indexed column, counted in characters
+    pub column: usize,
+    /// 0-indexed byte offset from the start of the file
+    pub byte: usize,
+}
+
+impl Position {
+    /// The position of byte offset `byte` in `source`.
+    pub fn at_byte(source: &str, byte: usize) -> Self {
+        let byte = char_boundary(source, byte);
+        let before = &source[..byte];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Position {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            byte,
+        }
+    }
+}
+
+/// `byte` clamped to `source` and walked back to the nearest char boundary, so
+/// a span mapped onto text that changed since it was analyzed can't split a character.
+pub(crate) fn char_boundary(source: &str, byte: usize) -> usize {
+    let mut byte = byte.min(source.len());
+    while !source.is_char_boundary(byte) {
+        byte -= 1;
+    }
+    byte
+}
+
+/// A range of source text in one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub file: PathBuf,
+    pub start: Position,
+    /// Exclusive end
+    pub end: Position,
+}
+
+impl Span {
+    /// The span of a token parsed from `file`.
+    pub fn from_syn(file: &Path, span: proc_macro2::Span) -> Self {
+        let (start, end) = (span.start(), span.end());
+        let bytes = span.byte_range();
+        Span {
+            file: file.to_path_buf(),
+            start: Position { line: start.line, column: start.column + 1, byte: bytes.start },
+            end: Position { line: end.line, column: end.column + 1, byte: bytes.end },
+        }
+    }
+
+    /// The span of the byte range `bytes` of `source`, the text of `file`.
+    pub fn from_bytes(file: &Path, source: &str, bytes: std::ops::Range<usize>) -> Self {
+        Span {
+            file: file.to_path_buf(),
+            start: Position::at_byte(source, bytes.start),
+            end: Position::at_byte(source, bytes.end),
+        }
+    }
+
+    /// A span for diagnostics about a file as a whole, with no location in it.
+    pub fn whole_file(file: &Path) -> Self {
+        Span {
+            file: file.to_path_buf(),
+            start: Position::default(),
+            end: Position::default(),
         }
     }
 }
 
+/// A secondary span with an explanation, e.g. "`Shape` defined here"
+#[derive(Debug, Clone, Serialize)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
 /// A diagnostic produced by rustpeek
 #[derive(Debug, Clone, Serialize)]
 pub struct Diagnostic {
     pub severity: Severity,
-    pub file: PathBuf,
-    pub line: usize,
-    pub column: usize,
-    pub message: String,
+    /// Stable rustpeek rule ID, e.g. `unresolved-import` (see `rules::RULES`)
+    pub rule: String,
+    /// The rustc error code this diagnostic mirrors, if any
     pub error_code: Option<String>,
-    pub hint: Option<String>,
+    pub message: String,
+    /// Where the problem is
+    pub span: Span,
+    /// Related locations, e.g. where the item involved is defined
+    pub labels: Vec<Label>,
+    /// Additional context
+    pub notes: Vec<String>,
+    /// Suggestions for resolving the problem
+    pub help: Vec<String>,
     /// Optional auto-fix for this diagnostic
     #[serde(skip_serializing_if = "Option::is_none")]
     pub fix: Option<Fix>,
 }
 
+impl Diagnostic {
+    pub fn new(severity: Severity, rule: &str, span: Span, message: String) -> Self {
+        Diagnostic {
+            severity,
+            rule: rule.to_string(),
+            error_code: None,
+            message,
+            span,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+            fix: None,
+        }
+    }
+
+    pub fn error(rule: &str, span: Span, message: String) -> Self {
+        Self::new(Severity::Error, rule, span, message)
+    }
+
+    pub fn suggestion(rule: &str, span: Span, message: String) -> Self {
+        Self::new(Severity::Suggestion, rule, span, message)
+    }
+
+    pub fn with_code(mut self, code: &str) -> Self {
+        self.error_code = Some(code.to_string());
+        self
+    }
+
+    /// Whether `name` is this diagnostic's rule ID or error code, in any case.
+    pub fn is_named(&self, name: &str) -> bool {
+        name.eq_ignore_ascii_case(&self.rule)
+            || self.error_code.as_deref().is_some_and(|code| name.eq_ignore_ascii_case(code))
+    }
+
+    pub fn with_label(mut self, span: Span, message: String) -> Self {
+        self.labels.push(Label { span, message });
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn with_help(mut self, help: String) -> Self {
+        self.help.push(help);
+        self
+    }
+
+    pub fn with_fix(mut self, fix: Option<Fix>) -> Self {
+        self.fix = fix;
+        self
+    }
+
+    /// File the diagnostic is reported in
+    pub fn file(&self) -> &Path {
+        &self.span.file
+    }
+
+    /// Line of the start of the primary span (1-indexed)
+    pub fn line(&self) -> usize {
+        self.span.start.line
+    }
+
+    /// Column of the start of the primary span (1-indexed)
+    pub fn column(&self) -> usize {
+        self.span.start.column
+    }
+}
+
+/// Renders like rustc, with source snippets read from disk and no color.
 impl std::fmt::Display for Diagnostic {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let severity = match self.severity {
-            Severity::Error => "error",
-            Severity::Suggestion => "suggestion",
-        };
-        let code = self.error_code.as_deref().unwrap_or("");
-        let code_part = if code.is_empty() {
-            String::new()
-        } else {
-            format!("[{code}] ")
-        };
-        write!(
-            f,
-            "{severity}: {code_part}{msg}\n --> {file}:{line}:{col}",
-            msg = self.message,
-            file = self.file.display(),
-            line = self.line,
-            col = self.column,
-        )?;
-        if let Some(hint) = &self.hint {
-            write!(f, "\n   = hint: {hint}")?;
-        }
-        Ok(())
+        f.write_str(&crate::render::Renderer::new(false).render(self))
     }
 }
 
@@ -339,8 +522,13 @@ impl AnalysisResult {
             .count()
     }
 
+    /// Diagnostics with a fix `fix` applies without being asked to, that is
+    /// a machine-applicable one.
     pub fn fixable_count(&self) -> usize {
-        self.diagnostics.iter().filter(|d| d.fix.is_some()).count()
+        self.diagnostics
+            .iter()
+            .filter(|d| d.fix.as_ref().is_some_and(|fix| fix.applicability == Applicability::MachineApplicable))
+            .count()
     }
 
     pub fn fixes(&self) -> Vec<&Fix> {
@@ -1,14 +1,18 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
 use std::path::Path;
 
 use syn::visit::Visit;
 
 use crate::discovery;
+use crate::similar::{self, Suggestions};
 use crate::types::*;
 
 /// Validate references in a file's AST against the symbol table.
 pub fn validate_file(
     ast: &syn::File,
     file_path: &Path,
+    source: &str,
     module_path: &ModulePath,
     symbols: &SymbolTable,
     src_dir: &Path,
@@ -16,11 +20,12 @@ pub fn validate_file(
 ) -> Vec<Diagnostic> {
     let mut visitor = ValidationVisitor {
         diagnostics: Vec::new(),
+        ast,
         file_path,
         module_path,
         symbols,
         src_dir,
-        source_lines: None,
+        source,
         crate_name,
     };
 
@@ -33,52 +38,18 @@ pub fn validate_file(
 
 struct ValidationVisitor<'a> {
     diagnostics: Vec<Diagnostic>,
+    ast: &'a syn::File,
     file_path: &'a Path,
     module_path: &'a ModulePath,
     symbols: &'a SymbolTable,
     src_dir: &'a Path,
-    /// Lazily loaded source lines for fix generation
-    source_lines: Option<Vec<String>>,
+    /// Source text of the file being validated
+    source: &'a str,
     /// The crate's own name (from Cargo.toml), so `use <name>::...` is treated as `use crate::...`
     crate_name: Option<&'a str>,
 }
 
 impl<'a> ValidationVisitor<'a> {
-    /// Get source lines, loading lazily.
-    fn source_lines(&mut self) -> &[String] {
-        if self.source_lines.is_none() {
-            let content = std::fs::read_to_string(self.file_path).unwrap_or_default();
-            self.source_lines = Some(content.lines().map(String::from).collect());
-        }
-        self.source_lines.as_deref().unwrap()
-    }
-
-    /// Find the best line to insert a `use` statement in the current file.
-    /// Returns the line number to insert BEFORE (1-indexed).
-    fn find_use_insert_line(&mut self) -> usize {
-        let lines = self.source_lines().to_vec();
-        let mut last_use_line = 0;
-        let mut last_mod_line = 0;
-
-        for (i, line) in lines.iter().enumerate() {
-            let trimmed = line.trim();
-            if trimmed.starts_with("use ") {
-                last_use_line = i + 1; // 1-indexed
-            }
-            if trimmed.starts_with("mod ") || trimmed.starts_with("pub mod ") {
-                last_mod_line = i + 1;
-            }
-        }
-
-        if last_use_line > 0 {
-            last_use_line + 1 // Insert after last use
-        } else if last_mod_line > 0 {
-            last_mod_line + 1 // Insert after last mod
-        } else {
-            1 // Top of file
-        }
-    }
-
     /// Check that `mod foo;` declarations have corresponding files.
     fn validate_mod_declarations(&mut self, ast: &syn::File) {
         for item in &ast.items {
@@ -92,19 +63,12 @@ impl<'a> ValidationVisitor<'a> {
                     discovery::resolve_mod_file(self.src_dir, self.module_path, &mod_name);
 
                 if resolved.is_none() {
-                    let span = m.ident.span();
-                    self.diagnostics.push(Diagnostic {
-                        severity: Severity::Error,
-                        file: self.file_path.to_path_buf(),
-                        line: span.start().line,
-                        column: span.start().column + 1,
-                        message: format!("file not found for module `{mod_name}`"),
-                        error_code: Some("E0583".to_string()),
-                        hint: Some(format!(
-                            "expected `{mod_name}.rs` or `{mod_name}/mod.rs`"
-                        )),
-                        fix: None,
-                    });
+                    let span = self.span(m.ident.span());
+                    self.diagnostics.push(
+                        Diagnostic::error("missing-module", span, format!("file not found for module `{mod_name}`"))
+                            .with_code("E0583")
+                            .with_help(format!("create `{mod_name}.rs` or `{mod_name}/mod.rs`")),
+                    );
                 }
             }
         }
@@ -134,12 +98,12 @@ impl<'a> ValidationVisitor<'a> {
             syn::UseTree::Name(n) => {
                 let mut full_path = prefix.clone();
                 full_path.push(n.ident.to_string());
-                self.check_use_path(&full_path, n.ident.span());
+                self.check_use_path(&full_path, n.ident.span(), false);
             }
             syn::UseTree::Rename(r) => {
                 let mut full_path = prefix.clone();
                 full_path.push(r.ident.to_string());
-                self.check_use_path(&full_path, r.ident.span());
+                self.check_use_path(&full_path, r.ident.span(), true);
             }
             syn::UseTree::Glob(_) => {
                 if is_crate_path(prefix, self.crate_name) {
@@ -148,20 +112,15 @@ impl<'a> ValidationVisitor<'a> {
                         let parent_path = ModulePath(prefix[..prefix.len() - 1].to_vec());
                         let last = prefix.last().map(|s| s.as_str()).unwrap_or("");
                         if self.symbols.find_in_module(&parent_path, last).is_none() {
-                            let span = use_item.use_token.span;
-                            self.diagnostics.push(Diagnostic {
-                                severity: Severity::Error,
-                                file: self.file_path.to_path_buf(),
-                                line: span.start().line,
-                                column: span.start().column + 1,
-                                message: format!(
-                                    "unresolved glob import `{}::*`",
-                                    prefix.join("::")
-                                ),
-                                error_code: Some("E0432".to_string()),
-                                hint: None,
-                                fix: None,
-                            });
+                            let span = self.span(use_item.use_token.span);
+                            self.diagnostics.push(
+                                Diagnostic::error(
+                                    "unresolved-import",
+                                    span,
+                                    format!("unresolved glob import `{}::*`", prefix.join("::")),
+                                )
+                                .with_code("E0432"),
+                            );
                         }
                     }
                 }
@@ -174,8 +133,9 @@ impl<'a> ValidationVisitor<'a> {
         }
     }
 
-    /// Check if a `use` path resolves within the crate.
-    fn check_use_path(&mut self, path: &[String], span: proc_macro2::Span) {
+    /// Check if a `use` path resolves within the crate. `renamed` is set for
+    /// `use path as alias`, which binds the alias rather than the last segment.
+    fn check_use_path(&mut self, path: &[String], span: proc_macro2::Span, renamed: bool) {
         if !is_crate_path(path, self.crate_name) {
             return;
         }
@@ -194,12 +154,10 @@ impl<'a> ValidationVisitor<'a> {
         let module_segments = &resolved[..resolved.len() - 1];
         let module_path = ModulePath(module_segments.to_vec());
 
-        // `use foo::self` means "import the module itself" — valid if the module exists
-        if item_name == "self" {
-            if self.symbols.modules.contains_key(&module_path) {
-                return;
-            }
-            // Fall through to "unresolved module" error below
+        // `use foo::self` means "import the module itself" — valid if the module exists.
+        // Otherwise fall through to the "unresolved module" error below
+        if item_name == "self" && self.symbols.modules.contains_key(&module_path) {
+            return;
         }
 
         if let Some(module_info) = self.symbols.modules.get(&module_path) {
@@ -214,61 +172,63 @@ impl<'a> ValidationVisitor<'a> {
                     return;
                 }
 
-                // Find similar names for hint and potential fix
-                let similar = self.find_similar_in_module(&module_path, item_name);
-                let fix = similar.as_ref().and_then(|hint_msg| {
-                    // Extract the suggested name from "did you mean `Foo`?"
-                    let suggested = hint_msg
-                        .strip_prefix("did you mean `")
-                        .and_then(|s| s.strip_suffix("`?"));
-                    suggested.map(|correct_name| {
-                        Fix::ReplaceLine {
+                let mut diag = Diagnostic::error(
+                    "unresolved-import",
+                    self.span(span),
+                    format!("unresolved import `{}`", path.join("::")),
+                )
+                .with_code("E0432");
+
+                // Similar names give a hint, and a fix if one is clearly closest
+                let similar = self.similar_in_module(&module_path, item_name);
+                if let Some(similar) = similar.best() {
+                    let fix = Fix::new(
+                        format!("replace `{item_name}` with `{}`", similar.name),
+                        Applicability::MaybeIncorrect,
+                        vec![TextEdit {
                             file: self.file_path.to_path_buf(),
-                            line: span.start().line,
-                            old_text: item_name.clone(),
-                            new_text: correct_name.to_string(),
-                        }
-                    })
-                });
-
-                self.diagnostics.push(Diagnostic {
-                    severity: Severity::Error,
-                    file: self.file_path.to_path_buf(),
-                    line: span.start().line,
-                    column: span.start().column + 1,
-                    message: format!("unresolved import `{}`", path.join("::")),
-                    error_code: Some("E0432".to_string()),
-                    hint: similar,
-                    fix,
-                });
+                            byte_range: span.byte_range(),
+                            replacement: similar.name.clone(),
+                        }],
+                    );
+                    // Importing a name the module already has would only trade this error for E0252
+                    let fix = if renamed {
+                        Some(fix)
+                    } else if self.binds_name(&similar.name) {
+                        None
+                    } else {
+                        Some(fix.binding(similar.name.clone()))
+                    };
+                    diag = diag.with_fix(fix);
+                }
+                for item in similar.shown() {
+                    diag = diag.with_label(item.span.clone(), format!("`{}` defined here", item.name));
+                }
+                if !similar.is_empty() {
+                    diag = diag.with_help(similar::did_you_mean(similar.shown().map(|item| item.name.as_str())));
+                }
+                self.diagnostics.push(diag);
             } else {
                 // Item exists — check visibility
-                if let Some(item) = module_info.items.iter().find(|i| i.name == *item_name) {
-                    if !item.vis.accessible_from(&item.module, self.module_path) {
-                        self.diagnostics.push(Diagnostic {
-                            severity: Severity::Error,
-                            file: self.file_path.to_path_buf(),
-                            line: span.start().line,
-                            column: span.start().column + 1,
-                            message: format!("`{}` is private", path.join("::")),
-                            error_code: Some("E0603".to_string()),
-                            hint: None,
-                            fix: None,
-                        });
-                    }
+                if let Some(item) = module_info.items.iter().find(|i| i.name == *item_name)
+                    && !item.vis.accessible_from(&item.module, self.module_path)
+                {
+                    self.diagnostics.push(
+                        Diagnostic::error("private-import", self.span(span), format!("`{}` is private", path.join("::")))
+                            .with_code("E0603")
+                            .with_label(item.span.clone(), format!("`{item_name}` defined here")),
+                    );
                 }
             }
         } else {
-            self.diagnostics.push(Diagnostic {
-                severity: Severity::Error,
-                file: self.file_path.to_path_buf(),
-                line: span.start().line,
-                column: span.start().column + 1,
-                message: format!("unresolved module `{}`", module_segments.join("::")),
-                error_code: Some("E0433".to_string()),
-                hint: None,
-                fix: None,
-            });
+            self.diagnostics.push(
+                Diagnostic::error(
+                    "unresolved-module",
+                    self.span(span),
+                    format!("unresolved module `{}`", module_segments.join("::")),
+                )
+                .with_code("E0433"),
+            );
         }
     }
 
@@ -276,73 +236,94 @@ impl<'a> ValidationVisitor<'a> {
     fn validate_references(&mut self, ast: &syn::File) {
         let mut ref_visitor = RefVisitor {
             validator: self,
+            locals: Vec::new(),
+            inline_mods: Vec::new(),
         };
         syn::visit::visit_file(&mut ref_visitor, ast);
     }
 
+    fn span(&self, span: proc_macro2::Span) -> Span {
+        Span::from_syn(self.file_path, span)
+    }
+
     /// Resolve a use path to an absolute crate path.
     fn resolve_use_path(&self, path: &[String]) -> Option<Vec<String>> {
-        if path.is_empty() {
-            return None;
-        }
+        resolve_use_path(path, self.module_path, self.symbols, self.crate_name)
+    }
 
-        let first = path[0].as_str();
+    /// Items in a module whose names are similar, for "did you mean?" suggestions.
+    fn similar_in_module(&self, module: &ModulePath, name: &str) -> Suggestions<&'a ItemInfo> {
+        let items = self.symbols.modules.get(module).map(|module_info| module_info.items.as_slice()).unwrap_or_default();
+        Suggestions::new(name, items, |item| &item.name)
+    }
 
-        // Treat the crate's own name as `crate`
-        let is_own_name = self.crate_name.is_some_and(|name| name == first);
+    /// Whether the module defines or (not glob-)imports `name` itself.
+    fn binds_name(&self, name: &str) -> bool {
+        self.symbols.modules.get(self.module_path).is_some_and(|module_info| {
+            module_info.items.iter().any(|item| item.name == name)
+                || module_info.uses.iter().any(|u| !u.is_glob && u.alias == name)
+        })
+    }
 
-        if first == "crate" || is_own_name {
-            if is_own_name {
-                // Replace crate name with "crate" for uniform lookup
-                let mut resolved = vec!["crate".to_string()];
-                resolved.extend(path[1..].iter().cloned());
-                Some(resolved)
-            } else {
-                Some(path.to_vec())
+    /// Whether the module glob-imports from outside the crate, which could bring any name into scope.
+    fn has_external_glob(&self) -> bool {
+        self.symbols.modules.get(self.module_path).is_some_and(|module_info| {
+            module_info.uses.iter().any(|u| u.is_glob && self.resolve_use_path(&u.path).is_none())
+        })
+    }
+
+    /// Whether a glob import could bring `name` into the module: a glob from
+    /// another crate or of an enum, or of a module of this crate that defines
+    /// or imports it, directly or through its own globs.
+    fn could_be_glob_imported(&self, name: &str) -> bool {
+        let mut seen = HashSet::new();
+        let mut pending = vec![self.module_path.clone()];
+        while let Some(module) = pending.pop() {
+            if !seen.insert(module.clone()) {
+                continue;
             }
-        } else {
-            match first {
-                "self" => {
-                    let mut resolved = self.module_path.0.clone();
-                    resolved.extend(path[1..].iter().cloned());
-                    Some(resolved)
-                }
-                "super" => {
-                    if let Some(parent) = self.module_path.parent() {
-                        let mut resolved = parent.0;
-                        resolved.extend(path[1..].iter().cloned());
-                        Some(resolved)
-                    } else {
-                        None
-                    }
-                }
-                _ => {
-                    let candidate = self.module_path.child(first);
-                    if self.symbols.modules.contains_key(&candidate) {
-                        let mut resolved = candidate.0;
-                        resolved.extend(path[1..].iter().cloned());
-                        return Some(resolved);
-                    }
-                    None
+            let Some(module_info) = self.symbols.modules.get(&module) else {
+                return true;
+            };
+            for u in module_info.uses.iter().filter(|u| u.is_glob) {
+                let Some(resolved) = resolve_use_path(&u.path, &module, self.symbols, self.crate_name) else {
+                    return true;
+                };
+                let target = ModulePath(resolved);
+                let Some(target_info) = self.symbols.modules.get(&target) else {
+                    return true;
+                };
+                if target_info.items.iter().any(|item| item.name == name)
+                    || target_info.uses.iter().any(|u| !u.is_glob && u.alias == name)
+                {
+                    return true;
                 }
+                pending.push(target);
             }
         }
+        false
     }
 
-    /// Find similar item names in a module for "did you mean?" suggestions.
-    fn find_similar_in_module(&self, module: &ModulePath, name: &str) -> Option<String> {
-        let module_info = self.symbols.modules.get(module)?;
-        let similar: Vec<_> = module_info
-            .items
-            .iter()
-            .filter(|i| is_similar(&i.name, name))
-            .collect();
-
-        if similar.len() == 1 {
-            Some(format!("did you mean `{}`?", similar[0].name))
-        } else {
-            None
+    /// Whether a `macro_rules!` macro named `name` is textually in scope: one
+    /// defined in this module or a module enclosing it, or in a `#[macro_use]`
+    /// module declared in one of them.
+    fn macro_in_scope(&self, name: &str) -> bool {
+        let mut module = Some(self.module_path.clone());
+        while let Some(current) = module {
+            if self.defines_macro(&current, name) {
+                return true;
+            }
+            module = current.parent();
         }
+        false
+    }
+
+    /// Whether `module`, or a `#[macro_use]` module declared in it, defines a macro named `name`.
+    fn defines_macro(&self, module: &ModulePath, name: &str) -> bool {
+        self.symbols.modules.get(module).is_some_and(|module_info| {
+            module_info.items.iter().any(|item| item.kind == ItemKind::Macro && item.name == name)
+                || module_info.macro_use_modules.iter().any(|child| self.defines_macro(&module.child(child), name))
+        })
     }
 
     /// Resolve what names are in scope for a given module.
@@ -369,13 +350,13 @@ impl<'a> ValidationVisitor<'a> {
                         }
                     }
                 } else {
-                    if let Some(resolved) = &resolved {
-                        if resolved.len() >= 2 {
-                            let item_name = resolved.last().unwrap();
-                            let mod_path = ModulePath(resolved[..resolved.len() - 1].to_vec());
-                            if let Some(item) = self.symbols.find_in_module(&mod_path, item_name) {
-                                scope.push((use_info.alias.clone(), item));
-                            }
+                    if let Some(resolved) = &resolved
+                        && resolved.len() >= 2
+                    {
+                        let item_name = resolved.last().unwrap();
+                        let mod_path = ModulePath(resolved[..resolved.len() - 1].to_vec());
+                        if let Some(item) = self.symbols.find_in_module(&mod_path, item_name) {
+                            scope.push((use_info.alias.clone(), item));
                         }
                     }
                 }
@@ -402,84 +383,136 @@ impl<'a> ValidationVisitor<'a> {
 /// Visitor that walks expressions looking for references to validate.
 struct RefVisitor<'a, 'b> {
     validator: &'a mut ValidationVisitor<'b>,
+    /// Names bound in each item being visited, innermost last
+    locals: Vec<Locals>,
+    /// The inline modules being visited, outermost first
+    inline_mods: Vec<String>,
 }
 
 impl<'a, 'b, 'ast> Visit<'ast> for RefVisitor<'a, 'b> {
+    fn visit_item(&mut self, node: &'ast syn::Item) {
+        let mut locals = Locals::default();
+        locals.visit_item(node);
+        self.locals.push(locals);
+        syn::visit::visit_item(self, node);
+        self.locals.pop();
+    }
+
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        let inline = node.content.is_some();
+        if inline {
+            self.inline_mods.push(node.ident.to_string());
+        }
+        syn::visit::visit_item_mod(self, node);
+        if inline {
+            self.inline_mods.pop();
+        }
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        if node.path.leading_colon.is_none() && node.path.segments.len() == 1 {
+            let ident = &node.path.segments[0].ident;
+            let name = ident.to_string();
+            if name != "macro_rules" && !STD_MACROS.contains(&name.as_str()) {
+                self.check_type_in_scope(&name, ident.span(), Usage::Macro);
+            }
+        }
+
+        syn::visit::visit_macro(self, node);
+    }
+
     fn visit_expr_struct(&mut self, node: &'ast syn::ExprStruct) {
+        if node.qself.is_none() && node.path.segments.len() == 1 {
+            let ident = &node.path.segments[0].ident;
+            let name = ident.to_string();
+            if name.starts_with(char::is_uppercase) {
+                let fields: Vec<String> = node
+                    .fields
+                    .iter()
+                    .filter_map(|f| match &f.member {
+                        syn::Member::Named(ident) => Some(ident.to_string()),
+                        syn::Member::Unnamed(_) => None,
+                    })
+                    .collect();
+                self.check_type_in_scope(&name, ident.span(), Usage::Struct(&fields));
+            }
+        }
+
         let type_name = path_last_segment(&node.path);
         if let Some(type_name) = type_name {
-            let struct_fields: Option<Vec<String>> = {
+            let struct_fields: Option<(Vec<String>, Span)> = {
                 let scope = self.validator.names_in_scope();
                 scope.iter()
                     .find(|(n, i)| *n == type_name && i.kind == ItemKind::Struct)
-                    .map(|(_, item)| item.fields.iter().map(|f| f.name.clone()).collect())
+                    .map(|(_, item)| (item.fields.iter().map(|f| f.name.clone()).collect(), item.span.clone()))
             };
 
-            if let Some(expected_fields) = struct_fields {
-                if node.rest.is_none() {
-                    let provided: Vec<String> = node
-                        .fields
-                        .iter()
-                        .filter_map(|f| {
-                            if let syn::Member::Named(ident) = &f.member {
-                                Some(ident.to_string())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect();
-
-                    for field_name in &expected_fields {
-                        if !provided.contains(field_name) {
-                            let span = node.path.segments.last().map(|s| s.ident.span())
-                                .unwrap_or_else(proc_macro2::Span::call_site);
-                            self.validator.diagnostics.push(Diagnostic {
-                                severity: Severity::Error,
-                                file: self.validator.file_path.to_path_buf(),
-                                line: span.start().line,
-                                column: span.start().column + 1,
-                                message: format!(
-                                    "missing field `{field_name}` in initializer of `{type_name}`"
-                                ),
-                                error_code: Some("E0063".to_string()),
-                                hint: None,
-                                fix: None,
-                            });
+            if let Some((expected_fields, struct_span)) = struct_fields
+                && node.rest.is_none()
+            {
+                let provided: Vec<String> = node
+                    .fields
+                    .iter()
+                    .filter_map(|f| {
+                        if let syn::Member::Named(ident) = &f.member {
+                            Some(ident.to_string())
+                        } else {
+                            None
                         }
+                    })
+                    .collect();
+
+                for field_name in &expected_fields {
+                    if !provided.contains(field_name) {
+                        let span = node.path.segments.last().map(|s| s.ident.span())
+                            .unwrap_or_else(proc_macro2::Span::call_site);
+                        self.validator.diagnostics.push(
+                            Diagnostic::error(
+                                "missing-field",
+                                self.validator.span(span),
+                                format!("missing field `{field_name}` in initializer of `{type_name}`"),
+                            )
+                            .with_code("E0063")
+                            .with_label(struct_span.clone(), format!("`{type_name}` defined here")),
+                        );
                     }
+                }
 
-                    for provided_name in &provided {
-                        if !expected_fields.contains(provided_name) {
-                            let span = node.fields.iter()
-                                .find(|f| {
-                                    if let syn::Member::Named(ident) = &f.member {
-                                        ident == provided_name
-                                    } else {
-                                        false
-                                    }
-                                })
-                                .map(|f| {
-                                    if let syn::Member::Named(ident) = &f.member {
-                                        ident.span()
-                                    } else {
-                                        proc_macro2::Span::call_site()
-                                    }
-                                })
-                                .unwrap_or_else(proc_macro2::Span::call_site);
-
-                            self.validator.diagnostics.push(Diagnostic {
-                                severity: Severity::Error,
-                                file: self.validator.file_path.to_path_buf(),
-                                line: span.start().line,
-                                column: span.start().column + 1,
-                                message: format!(
-                                    "struct `{type_name}` has no field named `{provided_name}`"
-                                ),
-                                error_code: Some("E0609".to_string()),
-                                hint: None,
-                                fix: None,
-                            });
+                for provided_name in &provided {
+                    if !expected_fields.contains(provided_name) {
+                        let span = node.fields.iter()
+                            .find(|f| {
+                                if let syn::Member::Named(ident) = &f.member {
+                                    ident == provided_name
+                                } else {
+                                    false
+                                }
+                            })
+                            .map(|f| {
+                                if let syn::Member::Named(ident) = &f.member {
+                                    ident.span()
+                                } else {
+                                    proc_macro2::Span::call_site()
+                                }
+                            })
+                            .unwrap_or_else(proc_macro2::Span::call_site);
+
+                        let available: Vec<String> = expected_fields
+                            .iter()
+                            .filter(|f| !provided.contains(f))
+                            .map(|f| format!("`{f}`"))
+                            .collect();
+                        let mut diag = Diagnostic::error(
+                            "unknown-field",
+                            self.validator.span(span),
+                            format!("struct `{type_name}` has no field named `{provided_name}`"),
+                        )
+                        .with_code("E0609")
+                        .with_label(struct_span.clone(), format!("`{type_name}` defined here"));
+                        if !available.is_empty() {
+                            diag = diag.with_note(format!("available fields are: {}", available.join(", ")));
                         }
+                        self.validator.diagnostics.push(diag);
                     }
                 }
             }
@@ -493,35 +526,47 @@ impl<'a, 'b, 'ast> Visit<'ast> for RefVisitor<'a, 'b> {
     }
 
     fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
-        if let syn::Expr::Path(path) = &*node.func {
-            if let Some(fn_name) = path_last_segment(&path.path) {
-                let scope = self.validator.names_in_scope();
-                if let Some((_, item)) = scope.iter().find(|(n, i)| {
-                    *n == fn_name && i.kind == ItemKind::Function
-                }) {
-                    if let Some(expected) = item.param_count {
-                        let actual = node.args.len();
-                        if actual != expected {
-                            let span = path.path.segments.last().map(|s| s.ident.span())
-                                .unwrap_or_else(proc_macro2::Span::call_site);
-                            self.validator.diagnostics.push(Diagnostic {
-                                severity: Severity::Error,
-                                file: self.validator.file_path.to_path_buf(),
-                                line: span.start().line,
-                                column: span.start().column + 1,
-                                message: format!(
-                                    "function `{fn_name}` takes {expected} argument(s) but {actual} were supplied"
-                                ),
-                                error_code: Some("E0061".to_string()),
-                                hint: None,
-                                fix: None,
-                            });
-                        }
-                    }
+        if let syn::Expr::Path(path) = &*node.func
+            && let Some(fn_name) = path_last_segment(&path.path)
+        {
+            let scope = self.validator.names_in_scope();
+            let function = scope
+                .iter()
+                .find(|(n, i)| *n == fn_name && i.kind == ItemKind::Function)
+                .and_then(|(_, item)| Some((item.param_count?, item.span.clone())));
+            if let Some((expected, fn_span)) = function {
+                let actual = node.args.len();
+                if actual != expected {
+                    let span = path.path.segments.last().map(|s| s.ident.span())
+                        .unwrap_or_else(proc_macro2::Span::call_site);
+                    self.validator.diagnostics.push(
+                        Diagnostic::error(
+                            "wrong-arg-count",
+                            self.validator.span(span),
+                            format!("function `{fn_name}` takes {expected} argument(s) but {actual} were supplied"),
+                        )
+                        .with_code("E0061")
+                        .with_label(fn_span, format!("`{fn_name}` defined here")),
+                    );
                 }
             }
         }
 
+        // A single-segment callee is checked as a call rather than as a plain value
+        if let syn::Expr::Path(callee) = &*node.func
+            && callee.qself.is_none() && callee.path.segments.len() == 1
+        {
+            let ident = &callee.path.segments[0].ident;
+            for attr in &node.attrs {
+                self.visit_attribute(attr);
+            }
+            self.check_type_in_scope(&ident.to_string(), ident.span(), Usage::Call);
+            for arg in &node.args {
+                self.visit_expr(arg);
+            }
+            return;
+        }
+
         syn::visit::visit_expr_call(self, node);
     }
 
@@ -531,56 +576,47 @@ impl<'a, 'b, 'ast> Visit<'ast> for RefVisitor<'a, 'b> {
             let type_name = node.path.segments[0].ident.to_string();
             let variant_name = node.path.segments[1].ident.to_string();
 
-            let check_result: Option<(Vec<String>, Vec<String>)> = {
+            let check_result: Option<(Vec<String>, Vec<String>, Span)> = {
                 let scope = self.validator.names_in_scope();
                 scope.iter()
                     .find(|(n, i)| *n == type_name && i.kind == ItemKind::Enum)
                     .map(|(_, item)| {
                         let variants: Vec<String> = item.variants.iter().map(|v| v.name.clone()).collect();
-                        let similar: Vec<String> = item.variants.iter()
-                            .filter(|v| is_similar(&v.name, &variant_name))
-                            .map(|v| v.name.clone())
-                            .collect();
-                        (variants, similar)
+                        let similar = Suggestions::new(&variant_name, &item.variants, |v| &v.name);
+                        let similar: Vec<String> = similar.shown().map(|v| v.name.clone()).collect();
+                        (variants, similar, item.span.clone())
                     })
             };
 
-            if let Some((variants, similar)) = check_result {
-                if !variants.contains(&variant_name) {
-                    let has_method = self.validator.symbols
-                        .find_methods(&type_name)
-                        .iter()
-                        .any(|(_, m)| m.name == variant_name);
-
-                    if !has_method {
-                        let span = node.path.segments[1].ident.span();
-                        self.validator.diagnostics.push(Diagnostic {
-                            severity: Severity::Error,
-                            file: self.validator.file_path.to_path_buf(),
-                            line: span.start().line,
-                            column: span.start().column + 1,
-                            message: format!(
-                                "no variant `{variant_name}` in enum `{type_name}`"
-                            ),
-                            error_code: Some("E0599".to_string()),
-                            hint: if similar.len() == 1 {
-                                Some(format!("did you mean `{}`?", similar[0]))
-                            } else {
-                                None
-                            },
-                            fix: None,
-                        });
+            if let Some((variants, similar, enum_span)) = check_result
+                && !variants.contains(&variant_name)
+            {
+                let has_method = self.validator.symbols
+                    .find_methods(&type_name)
+                    .iter()
+                    .any(|(_, m)| m.name == variant_name);
+
+                if !has_method {
+                    let span = self.validator.span(node.path.segments[1].ident.span());
+                    let mut diag = Diagnostic::error(
+                        "unknown-variant",
+                        span,
+                        format!("no variant `{variant_name}` in enum `{type_name}`"),
+                    )
+                    .with_code("E0599")
+                    .with_label(enum_span, format!("`{type_name}` defined here"));
+                    if !similar.is_empty() {
+                        diag = diag.with_help(similar::did_you_mean(similar.iter().map(String::as_str)));
                     }
+                    self.validator.diagnostics.push(diag);
                 }
             }
         }
 
-        // Check for unresolved types/paths that exist elsewhere in the crate
-        if node.path.segments.len() == 1 {
-            let name = node.path.segments[0].ident.to_string();
-            if name.starts_with(char::is_uppercase) {
-                self.check_type_in_scope(&name, node.path.segments[0].ident.span());
-            }
+        // Check for unresolved values that exist elsewhere in the crate
+        if node.qself.is_none() && node.path.segments.len() == 1 {
+            let ident = &node.path.segments[0].ident;
+            self.check_type_in_scope(&ident.to_string(), ident.span(), Usage::Value);
         }
 
         syn::visit::visit_expr_path(self, node);
@@ -590,7 +626,7 @@ impl<'a, 'b, 'ast> Visit<'ast> for RefVisitor<'a, 'b> {
         if node.qself.is_none() && node.path.segments.len() == 1 {
             let name = node.path.segments[0].ident.to_string();
             if name.starts_with(char::is_uppercase) {
-                self.check_type_in_scope(&name, node.path.segments[0].ident.span());
+                self.check_type_in_scope(&name, node.path.segments[0].ident.span(), Usage::Type);
             }
         }
 
@@ -602,129 +638,407 @@ impl<'a, 'b, 'ast> Visit<'ast> for RefVisitor<'a, 'b> {
     }
 }
 
+/// Where an unresolved name is used, which decides what kind of item it can name.
+enum Usage<'u> {
+    /// `x: Name`
+    Type,
+    /// `let x = Name;`
+    Value,
+    /// `Name(..)`
+    Call,
+    /// `Name { a, b }`, with the fields given
+    Struct(&'u [String]),
+    /// `name!(..)`
+    Macro,
+}
+
+impl Usage<'_> {
+    fn accepts(&self, item: &ItemInfo) -> bool {
+        match self {
+            Usage::Type => matches!(
+                item.kind,
+                ItemKind::Struct | ItemKind::Union | ItemKind::Enum | ItemKind::Trait | ItemKind::TypeAlias
+            ),
+            // Unit and tuple structs are values too; structs with named fields aren't
+            Usage::Value => match item.kind {
+                ItemKind::Struct => item.fields.is_empty(),
+                ItemKind::Const | ItemKind::Static | ItemKind::Function => true,
+                _ => false,
+            },
+            Usage::Call => {
+                item.kind == ItemKind::Function || (item.kind == ItemKind::Struct && item.param_count.is_some())
+            }
+            Usage::Struct(fields) => {
+                matches!(item.kind, ItemKind::Struct | ItemKind::Union) && fields.iter().all(|f| item.fields.iter().any(|field| field.name == *f))
+            }
+            Usage::Macro => item.kind == ItemKind::Macro,
+        }
+    }
+
+    /// Whether the name is used as a value or macro rather than as a type.
+    fn is_value(&self) -> bool {
+        matches!(self, Usage::Value | Usage::Call | Usage::Macro)
+    }
+}
+
+/// An item elsewhere in the crate that an unresolved name could refer to.
+struct Candidate {
+    /// The item's defining module
+    module: String,
+    /// The module to import it from, if it's visible from here
+    import: Option<String>,
+    span: Span,
+    /// Modules next to this one that already import it
+    sibling_imports: usize,
+    /// Leading modules its defining module shares with this one
+    proximity: usize,
+}
+
+impl Candidate {
+    /// Sort key, most likely candidate first
+    fn rank(&self) -> (bool, Reverse<usize>, Reverse<usize>, Option<usize>, &str) {
+        (
+            self.import.is_none(),
+            Reverse(self.sibling_imports),
+            Reverse(self.proximity),
+            self.import.as_ref().map(String::len),
+            &self.module,
+        )
+    }
+}
+
 impl<'a, 'b> RefVisitor<'a, 'b> {
-    /// Check if a type name is in scope; if not, suggest where it lives in the crate.
-    /// Generates a Fix (insert use statement) when there's a single unambiguous candidate,
-    /// or uses smart import resolution when there are multiple candidates.
-    fn check_type_in_scope(&mut self, name: &str, span: proc_macro2::Span) {
+    /// Check if a name is in scope; if not, suggest where it lives in the crate.
+    /// Only items that fit how the name is used are candidates, and names from
+    /// the std prelude are left alone. Generates a Fix (insert use statement)
+    /// when there's a single candidate and it can be imported; otherwise the
+    /// help lists the candidates, most likely first: importable ones, then those
+    /// that neighbouring modules import, then the ones defined nearest.
+    fn check_type_in_scope(&mut self, name: &str, span: proc_macro2::Span, usage: Usage) {
+        if matches!(name, "self" | "Self" | "super" | "crate") || STD_PRELUDE_VALUES.contains(&name) {
+            return;
+        }
+        // A crate item with a prelude name isn't what the name means unimported
+        if !matches!(usage, Usage::Macro) && (STD_PRELUDE_TYPES.contains(&name) || STD_PRELUDE_VARIANTS.contains(&name)) {
+            return;
+        }
+        if self.locals.iter().any(|locals| locals.names.contains(name)) {
+            return;
+        }
+
         let in_scope = {
             let scope = self.validator.names_in_scope();
             scope.iter().any(|(n, _)| *n == name)
         };
 
-        if in_scope {
+        if in_scope || self.validator.binds_name(name) {
+            return;
+        }
+        // A value could come from another crate's glob; a macro from an enclosing module
+        if usage.is_value() && self.validator.has_external_glob() {
+            return;
+        }
+        if matches!(usage, Usage::Macro) && self.validator.macro_in_scope(name) {
             return;
         }
 
-        let candidates: Vec<(String, ItemKind)> = self.validator
+        let validator = &self.validator;
+        // Inside an inline module, the import goes into it
+        let here = &self.inline_mods.iter().fold(validator.module_path.clone(), |path, name| path.child(name));
+        let mut candidates: Vec<Candidate> = validator
             .find_anywhere(name)
             .iter()
-            .map(|(path, item)| (path.display(), item.kind.clone()))
+            .filter(|(_, item)| usage.accepts(item))
+            .map(|(path, item)| Candidate {
+                module: path.display(),
+                import: crate::imports::import_path(validator.symbols, item, here, validator.crate_name),
+                span: item.span.clone(),
+                sibling_imports: crate::imports::sibling_imports(validator.symbols, item, here, validator.crate_name),
+                proximity: path.0.iter().zip(&here.0).take_while(|(a, b)| a == b).count(),
+            })
             .collect();
 
         if candidates.is_empty() {
-            return; // Not in crate — could be external, stay quiet
+            // Not in crate — could be external, unless nothing could have brought it into scope
+            if matches!(usage, Usage::Type | Usage::Struct(_)) && validator.find_anywhere(name).is_empty() {
+                self.check_unknown_name(name, span, &usage);
+            }
+            return;
         }
+        candidates.sort_by(|a, b| a.rank().cmp(&b.rank()));
+        // Definitions under different `#[cfg]`s are one candidate
+        let mut seen = HashSet::new();
+        candidates.retain(|c| seen.insert(c.import.clone().unwrap_or_else(|| c.module.clone())));
 
-        let (fix, hint) = if candidates.len() == 1 {
+        let (fix, hint) = match candidates.as_slice() {
             // Single candidate — high confidence auto-fix
-            let insert_line = self.validator.find_use_insert_line();
-            let use_path = &candidates[0].0;
-            let fix = Fix::InsertLine {
-                file: self.validator.file_path.to_path_buf(),
-                line: insert_line,
-                content: format!("use {use_path}::{name};\n"),
-            };
-            let hint = format!("add `use {use_path}::{name};`");
-            (Some(fix), hint)
-        } else {
-            // Multiple candidates — report all, no auto-fix
-            let locations: Vec<&str> = candidates.iter().map(|(p, _)| p.as_str()).collect();
-            let hint = format!(
-                "did you mean `{name}` from `{}`?",
-                locations.join("` or `")
-            );
-            (None, hint)
+            [Candidate { import: Some(use_path), .. }] => {
+                let edit = crate::imports::import_edit(
+                    validator.file_path,
+                    validator.source,
+                    validator.ast,
+                    &self.inline_mods,
+                    use_path,
+                    name,
+                    validator.crate_name,
+                );
+                let binds = self.inline_mods.iter().map(String::as_str).chain([name]).collect::<Vec<_>>().join("::");
+                let fix = Fix::new(format!("add `use {use_path}::{name};`"), Applicability::MachineApplicable, vec![edit])
+                    .binding(binds);
+                (Some(fix), format!("add `use {use_path}::{name};`"))
+            }
+            [candidate] => (None, format!("`{}::{name}` exists, but isn't visible from here", candidate.module)),
+            _ => {
+                // Multiple candidates — report all, no auto-fix
+                let locations: Vec<&str> =
+                    candidates.iter().map(|c| c.import.as_deref().unwrap_or(&c.module)).collect();
+                (None, format!("did you mean `{name}` from `{}`?", locations.join("` or `")))
+            }
         };
 
-        self.validator.diagnostics.push(Diagnostic {
-            severity: Severity::Suggestion,
-            file: self.validator.file_path.to_path_buf(),
-            line: span.start().line,
-            column: span.start().column + 1,
-            message: format!("cannot find `{name}` in this scope"),
-            error_code: Some("E0412".to_string()),
-            hint: Some(hint),
-            fix,
-        });
+        let (rule, code) = if usage.is_value() { ("value-not-imported", "E0425") } else { ("not-imported", "E0412") };
+        let message = match usage {
+            Usage::Macro => format!("cannot find macro `{name}` in this scope"),
+            _ => format!("cannot find `{name}` in this scope"),
+        };
+        let mut diag = Diagnostic::suggestion(rule, self.validator.span(span), message)
+            .with_code(code)
+            .with_help(hint)
+            .with_fix(fix);
+        for candidate in candidates {
+            diag = diag.with_label(candidate.span, format!("`{}::{name}` defined here", candidate.module));
+        }
+        self.validator.diagnostics.push(diag);
+    }
+
+    /// Report a type name that isn't defined in the crate and that no import,
+    /// glob, macro, prelude or generic parameter can bring into scope, if it
+    /// looks like a typo of a visible name.
+    fn check_unknown_name(&mut self, name: &str, span: proc_macro2::Span, usage: &Usage) {
+        if self.validator.could_be_glob_imported(name) {
+            return;
+        }
+        // Names from block-level `use`s, macro invocations and other unindexed items aren't known
+        if self.locals.iter().any(|locals| locals.opaque) || !all_indexed(&self.validator.ast.items) {
+            return;
+        }
+
+        let mut visible: Vec<String> = self
+            .validator
+            .names_in_scope()
+            .into_iter()
+            .filter(|(_, item)| usage.accepts(item))
+            .map(|(name, _)| name)
+            .collect();
+        if matches!(usage, Usage::Type) {
+            let locals = self.locals.iter().flat_map(|locals| &locals.names).filter(|local| local.starts_with(char::is_uppercase));
+            visible.extend(locals.cloned());
+            visible.extend(STD_PRELUDE_TYPES.iter().map(|name| name.to_string()));
+        }
+        visible.sort();
+        visible.dedup();
+        let similar = Suggestions::new(name, &visible, |visible| visible.as_str());
+        if similar.is_empty() {
+            return;
+        }
+
+        let mut diag = Diagnostic::error("unresolved-name", self.validator.span(span), format!("cannot find `{name}` in this scope"))
+            .with_code("E0412")
+            .with_help(similar::did_you_mean(similar.shown().map(|name| name.as_str())));
+        if let Some(similar) = similar.best() {
+            let fix = Fix::new(
+                format!("replace `{name}` with `{similar}`"),
+                Applicability::MaybeIncorrect,
+                vec![TextEdit {
+                    file: self.validator.file_path.to_path_buf(),
+                    byte_range: span.byte_range(),
+                    replacement: similar.to_string(),
+                }],
+            );
+            diag = diag.with_fix(Some(fix));
+        }
+        self.validator.diagnostics.push(diag);
     }
 }
 
-/// Check if a path starts with `crate`, `self`, `super`, or the crate's own name.
-fn is_crate_path(path: &[String], crate_name: Option<&str>) -> bool {
-    path.first().is_some_and(|s| {
-        s == "crate" || s == "self" || s == "super"
-            || crate_name.is_some_and(|name| name == s)
-    })
+/// Resolve a use path to an absolute crate path, relative to `module_path`.
+/// Returns None for paths that don't start in this crate (likely external).
+pub fn resolve_use_path(
+    path: &[String],
+    module_path: &ModulePath,
+    symbols: &SymbolTable,
+    crate_name: Option<&str>,
+) -> Option<Vec<String>> {
+    if path.is_empty() {
+        return None;
+    }
+
+    let first = path[0].as_str();
+
+    // Treat the crate's own name as `crate`
+    let is_own_name = crate_name.is_some_and(|name| name == first);
+
+    if first == "crate" || is_own_name {
+        if is_own_name {
+            // Replace crate name with "crate" for uniform lookup
+            let mut resolved = vec!["crate".to_string()];
+            resolved.extend(path[1..].iter().cloned());
+            Some(resolved)
+        } else {
+            Some(path.to_vec())
+        }
+    } else {
+        match first {
+            "self" => {
+                let mut resolved = module_path.0.clone();
+                resolved.extend(path[1..].iter().cloned());
+                Some(resolved)
+            }
+            "super" => {
+                let ups = path.iter().take_while(|segment| *segment == "super").count();
+                if ups >= module_path.0.len() {
+                    return None;
+                }
+                let mut resolved = module_path.0[..module_path.0.len() - ups].to_vec();
+                resolved.extend(path[ups..].iter().cloned());
+                Some(resolved)
+            }
+            _ => {
+                let candidate = module_path.child(first);
+                if symbols.modules.contains_key(&candidate) {
+                    let mut resolved = candidate.0;
+                    resolved.extend(path[1..].iter().cloned());
+                    return Some(resolved);
+                }
+                None
+            }
+        }
+    }
 }
 
-/// Get the last segment name from a path.
-fn path_last_segment(path: &syn::Path) -> Option<String> {
-    path.segments.last().map(|s| s.ident.to_string())
+/// Names bound inside an item: generic parameters, function parameters,
+/// `let`s and other patterns, closure parameters, and items and `use`s
+/// declared in function bodies.
+#[derive(Default)]
+struct Locals {
+    names: HashSet<String>,
+    /// Whether the item declares names rustpeek can't list: a `use` in a
+    /// block (which may be a glob) or an item the indexer skips
+    opaque: bool,
 }
 
-/// Levenshtein edit distance between two strings.
-fn levenshtein(a: &str, b: &str) -> usize {
-    let a_len = a.len();
-    let b_len = b.len();
+impl<'ast> Visit<'ast> for Locals {
+    fn visit_type_param(&mut self, node: &'ast syn::TypeParam) {
+        self.names.insert(node.ident.to_string());
+        syn::visit::visit_type_param(self, node);
+    }
 
-    if a_len == 0 { return b_len; }
-    if b_len == 0 { return a_len; }
+    fn visit_const_param(&mut self, node: &'ast syn::ConstParam) {
+        self.names.insert(node.ident.to_string());
+        syn::visit::visit_const_param(self, node);
+    }
 
-    let mut prev: Vec<usize> = (0..=b_len).collect();
-    let mut curr = vec![0; b_len + 1];
+    fn visit_pat_ident(&mut self, node: &'ast syn::PatIdent) {
+        // A capitalized identifier pattern is a unit struct, variant or const, not a binding
+        let name = node.ident.to_string();
+        if !name.starts_with(char::is_uppercase) {
+            self.names.insert(name);
+        }
+        syn::visit::visit_pat_ident(self, node);
+    }
 
-    for (i, ca) in a.chars().enumerate() {
-        curr[0] = i + 1;
-        for (j, cb) in b.chars().enumerate() {
-            let cost = if ca == cb { 0 } else { 1 };
-            curr[j + 1] = (prev[j] + cost)
-                .min(prev[j + 1] + 1)
-                .min(curr[j] + 1);
+    fn visit_item(&mut self, node: &'ast syn::Item) {
+        let ident = match node {
+            syn::Item::Const(i) => Some(&i.ident),
+            syn::Item::Enum(i) => Some(&i.ident),
+            syn::Item::Fn(i) => Some(&i.sig.ident),
+            syn::Item::Macro(i) => i.ident.as_ref(),
+            syn::Item::Static(i) => Some(&i.ident),
+            syn::Item::Struct(i) => Some(&i.ident),
+            syn::Item::Trait(i) => Some(&i.ident),
+            syn::Item::Type(i) => Some(&i.ident),
+            syn::Item::Union(i) => Some(&i.ident),
+            syn::Item::ExternCrate(i) => Some(i.rename.as_ref().map_or(&i.ident, |(_, rename)| rename)),
+            _ => None,
+        };
+        self.names.extend(ident.map(|ident| ident.to_string()));
+        if let syn::Item::Use(u) = node {
+            self.use_tree(&u.tree);
+            self.opaque = true;
+        }
+        if !is_indexed(node) {
+            self.opaque = true;
         }
-        std::mem::swap(&mut prev, &mut curr);
+        syn::visit::visit_item(self, node);
     }
+}
 
-    prev[b_len]
+impl Locals {
+    /// Record the names a `use` in a block binds.
+    fn use_tree(&mut self, tree: &syn::UseTree) {
+        match tree {
+            syn::UseTree::Path(p) => self.use_tree(&p.tree),
+            syn::UseTree::Name(n) => {
+                self.names.insert(n.ident.to_string());
+            }
+            syn::UseTree::Rename(r) => {
+                self.names.insert(r.rename.to_string());
+            }
+            syn::UseTree::Glob(_) => {}
+            syn::UseTree::Group(g) => g.items.iter().for_each(|tree| self.use_tree(tree)),
+        }
+    }
 }
 
-/// Check if two names are similar enough to suggest one for the other.
-/// Uses case-insensitive Levenshtein distance, scaled by name length.
-fn is_similar(a: &str, b: &str) -> bool {
-    if a == b {
-        return false; // exact match isn't "similar", it's the same
+/// Whether the indexer records the names an item declares. It skips
+/// `extern crate`, `extern` blocks, trait aliases and macro invocations.
+fn is_indexed(item: &syn::Item) -> bool {
+    match item {
+        syn::Item::ExternCrate(_) | syn::Item::ForeignMod(_) | syn::Item::TraitAlias(_) | syn::Item::Verbatim(_) => false,
+        syn::Item::Macro(m) => m.ident.is_some(),
+        _ => true,
     }
+}
 
-    let a_lower = a.to_lowercase();
-    let b_lower = b.to_lowercase();
+/// Whether every item, including those in inline modules, is indexed.
+fn all_indexed(items: &[syn::Item]) -> bool {
+    items.iter().all(|item| match item {
+        syn::Item::Mod(m) => m.content.as_ref().is_none_or(|(_, items)| all_indexed(items)),
+        item => is_indexed(item),
+    })
+}
 
-    // Case-insensitive exact match
-    if a_lower == b_lower {
-        return true;
-    }
+/// Types and traits in the std prelude, which are in scope everywhere.
+const STD_PRELUDE_TYPES: &[&str] = &[
+    "AsMut", "AsRef", "Box", "Clone", "Copy", "Default", "DoubleEndedIterator", "Drop", "Eq", "ExactSizeIterator",
+    "Extend", "Fn", "FnMut", "FnOnce", "From", "FromIterator", "Future", "Into", "IntoFuture", "IntoIterator",
+    "Iterator", "Option", "Ord", "PartialEq", "PartialOrd", "Result", "Send", "Sized", "String", "Sync", "ToOwned",
+    "ToString", "TryFrom", "TryInto", "Unpin", "Vec",
+];
 
-    let dist = levenshtein(&a_lower, &b_lower);
-    let max_len = a.len().max(b.len());
+/// Enum variants in the std prelude, which are in scope everywhere.
+const STD_PRELUDE_VARIANTS: &[&str] = &["Some", "None", "Ok", "Err"];
 
-    // Allow distance proportional to length:
-    //   len 1-4: distance <= 1
-    //   len 5-8: distance <= 2
-    //   len 9+:  distance <= 3
-    let threshold = match max_len {
-        0..=4 => 1,
-        5..=8 => 2,
-        _ => 3,
-    };
+/// Functions in the std prelude, which are in scope everywhere.
+const STD_PRELUDE_VALUES: &[&str] = &["drop"];
 
-    dist <= threshold
+/// Macros from std, which are in scope everywhere.
+const STD_MACROS: &[&str] = &[
+    "assert", "assert_eq", "assert_ne", "cfg", "column", "compile_error", "concat", "dbg", "debug_assert",
+    "debug_assert_eq", "debug_assert_ne", "env", "eprint", "eprintln", "file", "format", "format_args", "include",
+    "include_bytes", "include_str", "line", "matches", "module_path", "option_env", "panic", "print", "println",
+    "stringify", "thread_local", "todo", "unimplemented", "unreachable", "vec", "write", "writeln",
+];
+
+/// Check if a path starts with `crate`, `self`, `super`, or the crate's own name.
+fn is_crate_path(path: &[String], crate_name: Option<&str>) -> bool {
+    path.first().is_some_and(|s| {
+        s == "crate" || s == "self" || s == "super"
+            || crate_name.is_some_and(|name| name == s)
+    })
+}
+
+/// Get the last segment name from a path.
+fn path_last_segment(path: &syn::Path) -> Option<String> {
+    path.segments.last().map(|s| s.ident.to_string())
 }
@@ -0,0 +1,108 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+
+use crate::config::{CONFIG_FILE, Config};
+use crate::session::Session;
+use crate::types::AnalysisResult;
+
+/// How long the filesystem must stay quiet before a batch of changes is analyzed.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// One analysis run triggered by watch mode.
+pub struct WatchRun<'a> {
+    /// 1-based run counter; run 1 is the initial check
+    pub run: usize,
+    /// Files whose changes triggered this run (empty for the initial check)
+    pub changed: &'a [PathBuf],
+    /// Whether Cargo.toml or rustpeek.toml changed, forcing a full rediscovery
+    pub rediscovered: bool,
+    pub result: &'a AnalysisResult,
+}
+
+/// Analyze the crate, then re-analyze after every debounced batch of changes
+/// to its `.rs` files, Cargo.toml or rustpeek.toml. Calls `report` after each
+/// run. `overrides` are layered over the crate's config on every reload.
+/// Only returns if the watcher fails.
+pub fn watch(
+    crate_root: &Path,
+    changed_files: Option<&[PathBuf]>,
+    overrides: Config,
+    mut report: impl FnMut(&WatchRun),
+) -> notify::Result<()> {
+    let mut session = Session::new(crate_root, overrides);
+    let result = session.analyze(changed_files);
+    report(&WatchRun {
+        run: 1,
+        changed: &[],
+        rediscovered: false,
+        result: &result,
+    });
+    // The session keeps only rustpeek's own spans, so the locations
+    // proc_macro2 recorded while parsing can go after every run
+    proc_macro2::extra::invalidate_current_thread_spans();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&crate_root.join("src"), RecursiveMode::Recursive)?;
+    // Watch the directory rather than Cargo.toml itself: editors often save by
+    // replacing the file, which would silently drop a watch on the file.
+    watcher.watch(crate_root, RecursiveMode::NonRecursive)?;
+
+    // notify reports absolute paths; map them back onto `crate_root` so they
+    // match the paths discovery produced.
+    let absolute_root = crate_root.canonicalize()?;
+
+    let mut run = 1;
+    loop {
+        // Block for the first event, then drain until things go quiet
+        let mut events = vec![rx.recv().map_err(|e| notify::Error::generic(&e.to_string()))?];
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            events.push(event);
+        }
+
+        let mut changed: Vec<PathBuf> = Vec::new();
+        let mut manifest_changed = false;
+        for event in events {
+            let event = event?;
+            if event.kind.is_access() {
+                continue;
+            }
+            for path in event.paths {
+                let Ok(relative) = path.strip_prefix(&absolute_root) else {
+                    continue;
+                };
+                if relative == Path::new("Cargo.toml") || relative == Path::new(CONFIG_FILE) {
+                    manifest_changed = true;
+                } else if relative.extension().is_some_and(|ext| ext == "rs") {
+                    let path = crate_root.join(relative);
+                    if !changed.contains(&path) {
+                        changed.push(path);
+                    }
+                }
+            }
+        }
+
+        if changed.is_empty() && !manifest_changed {
+            continue;
+        }
+
+        if manifest_changed {
+            session.rediscover();
+        } else {
+            session.files_changed(&changed);
+        }
+
+        run += 1;
+        let result = session.analyze(changed_files);
+        report(&WatchRun {
+            run,
+            changed: &changed,
+            rediscovered: manifest_changed,
+            result: &result,
+        });
+        proc_macro2::extra::invalidate_current_thread_spans();
+    }
+}